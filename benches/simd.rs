@@ -0,0 +1,37 @@
+// scalar against simd Vec3 dot and cross products, run with `cargo bench --features wide`
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use raytracing_101::algebra::simd;
+use raytracing_101::prelude::*;
+use raytracing_101::random::Random;
+
+const PAIRS: usize = 4096;
+const ROUNDS: usize = 2000;
+
+fn time(name: &str, pairs: &[(Vec3<f64>, Vec3<f64>)], operation: impl Fn(Vec3<f64>, Vec3<f64>) -> f64) -> Duration {
+    let start = Instant::now();
+    let mut sum = 0.0;
+    for _ in 0..ROUNDS {
+        for &(a, b) in pairs {
+            sum += operation(black_box(a), black_box(b));
+        }
+    }
+    black_box(sum);
+    let elapsed = start.elapsed();
+    println!("{:<8} {:>10.2?} ({:.2} ns per call)", name, elapsed, elapsed.as_nanos() as f64 / (PAIRS * ROUNDS) as f64);
+    elapsed
+}
+
+fn main() {
+    let mut random = Random::new(1);
+    let mut random_vec3 = || Vec3 { x: random.range(-1.0, 1.0), y: random.range(-1.0, 1.0), z: random.range(-1.0, 1.0) };
+    let pairs: Vec<(Vec3<f64>, Vec3<f64>)> = (0..PAIRS).map(|_| (random_vec3(), random_vec3())).collect();
+
+    let scalar = time("dot", &pairs, |a, b| a.dot(b));
+    let wide = time("simd dot", &pairs, simd::dot);
+    println!("speedup  {:.2}x\n", scalar.as_secs_f64() / wide.as_secs_f64());
+
+    let scalar = time("cross", &pairs, |a, b| a.cross_product(b).x);
+    let wide = time("simd cross", &pairs, |a, b| simd::cross_product(a, b).x);
+    println!("speedup  {:.2}x", scalar.as_secs_f64() / wide.as_secs_f64());
+}
@@ -0,0 +1,77 @@
+use std::ops::Range;
+use crate::algebra::vec3::Vec3;
+use crate::ray::Ray;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: Vec3<f64>,
+    pub max: Vec3<f64>,
+}
+
+impl Aabb {
+    pub fn union(self: Aabb, other: Aabb) -> Aabb {
+        Aabb {
+            min: Vec3 {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+                z: self.min.z.min(other.min.z),
+            },
+            max: Vec3 {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+                z: self.max.z.max(other.max.z),
+            },
+        }
+    }
+
+    // slab test, the ray only hits the box if the three slab intervals overlap
+    pub fn hit(self: &Aabb, ray: &Ray, interval: Range<f64>) -> bool {
+        let origin = ray.origin;
+        let inverse_direction = ray.inverse_direction;
+        let slabs = [
+            (origin.x, inverse_direction.x, self.min.x, self.max.x),
+            (origin.y, inverse_direction.y, self.min.y, self.max.y),
+            (origin.z, inverse_direction.z, self.min.z, self.max.z),
+        ];
+
+        let (mut t_min, mut t_max) = (interval.start, interval.end);
+        for (origin, inverse_direction, min, max) in slabs {
+            let t0 = (min - origin) * inverse_direction;
+            let t1 = (max - origin) * inverse_direction;
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_max <= t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb {
+        Aabb {
+            min: Vec3 { x: -1.0, y: -1.0, z: 9.0 },
+            max: Vec3 { x: 1.0, y: 1.0, z: 11.0 },
+        }
+    }
+
+    #[test]
+    fn axis_aligned_ray_hits_and_misses() {
+        let origin = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
+        let forward = Vec3 { x: 0.0, y: 0.0, z: 1.0 };
+        assert!(unit_box().hit(&Ray::new(origin, forward), 0.0..f64::INFINITY));
+        assert!(!unit_box().hit(&Ray::new(origin, -forward), 0.0..f64::INFINITY));
+        assert!(!unit_box().hit(&Ray::new(Vec3 { x: 2.0, ..origin }, forward), 0.0..f64::INFINITY));
+    }
+
+    #[test]
+    fn box_beyond_the_interval_is_missed() {
+        let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.05, y: 0.0, z: 1.0 });
+        assert!(unit_box().hit(&ray, 0.0..f64::INFINITY));
+        assert!(!unit_box().hit(&ray, 0.0..5.0));
+    }
+}
@@ -1,2 +1,8 @@
 pub mod vec3;
-pub mod quadratic;
\ No newline at end of file
+pub mod quadratic;
+pub mod scalar;
+pub mod smoothstep;
+#[cfg(feature = "hash")]
+pub mod quantized;
+#[cfg(feature = "wide")]
+pub mod simd;
\ No newline at end of file
@@ -1,11 +1,32 @@
+// roots of ax² + bx + c, both infinite when there is none
+// a zero discriminant (e.g. a ray tangent to a sphere) gives the repeated root twice
 pub fn compute_quadratic(a: f64, b: f64, c: f64) -> (f64, f64) {
     let delta = (b*b) - (4.0*a*c);
 
     if delta < 0.0 {
         (f64::INFINITY, f64::INFINITY)
+    } else if delta == 0.0 {
+        let t = -b / (2.0*a);
+        (t, t)
     } else {
         let t1 = (-b + delta.sqrt()) / (2.0*a);
         let t2 = (-b - delta.sqrt()) / (2.0*a);
         (t1, t2)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_discriminant_gives_the_repeated_root() {
+        assert_eq!(compute_quadratic(1.0, -20.0, 100.0), (10.0, 10.0));
+    }
+
+    #[test]
+    fn roots_and_no_roots() {
+        assert_eq!(compute_quadratic(1.0, -3.0, 2.0), (2.0, 1.0));
+        assert_eq!(compute_quadratic(1.0, 0.0, 1.0), (f64::INFINITY, f64::INFINITY));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::algebra::vec3::Vec3;
+
+// a Vec3 snapped to a grid of the given step so it can be hashed and compared exactly,
+// points closer than the step usually share a key but can fall on both sides of a cell edge
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct QuantizedVec3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl QuantizedVec3 {
+    pub fn new(v: Vec3<f64>, step: f64) -> QuantizedVec3 {
+        QuantizedVec3 {
+            x: (v.x / step).round() as i64,
+            y: (v.y / step).round() as i64,
+            z: (v.z / step).round() as i64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+
+    #[test]
+    fn points_within_the_step_share_a_key() {
+        let step = 1e-3;
+        let a = Vec3 { x: 1.0, y: -2.0, z: 0.5 };
+        let b = Vec3 { x: 1.0002, y: -1.9998, z: 0.5001 };
+        assert_eq!(QuantizedVec3::new(a, step), QuantizedVec3::new(b, step));
+        assert_ne!(QuantizedVec3::new(a, step), QuantizedVec3::new(Vec3 { x: 1.002, ..a }, step));
+    }
+
+    #[test]
+    fn close_vertices_are_deduplicated() {
+        let step = 1e-3;
+        let vertices = [
+            Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+            Vec3 { x: 1.0, y: 0.0, z: 0.0 },
+            Vec3 { x: 0.0001, y: -0.0001, z: 0.0 },
+            Vec3 { x: 0.9999, y: 0.0, z: 0.0002 },
+        ];
+        let mut indices: HashMap<QuantizedVec3, usize> = HashMap::new();
+        let mapped: Vec<usize> = vertices
+            .iter()
+            .map(|&vertex| {
+                let next = indices.len();
+                *indices.entry(QuantizedVec3::new(vertex, step)).or_insert(next)
+            })
+            .collect();
+        assert_eq!(indices.len(), 2);
+        assert_eq!(mapped, [0, 1, 0, 1]);
+    }
+}
@@ -0,0 +1,124 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// the float operations the algebra module needs, implemented for f32 and f64
+pub trait Scalar:
+    Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    // lossy for f32
+    fn from_f64(value: f64) -> Self;
+
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn signum(self) -> Self;
+    fn recip(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn acos(self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($t:ty) => {
+        impl Scalar for $t {
+            fn zero() -> $t {
+                0.0
+            }
+
+            fn one() -> $t {
+                1.0
+            }
+
+            fn from_f64(value: f64) -> $t {
+                value as $t
+            }
+
+            fn sqrt(self) -> $t {
+                <$t>::sqrt(self)
+            }
+
+            fn abs(self) -> $t {
+                <$t>::abs(self)
+            }
+
+            fn min(self, other: $t) -> $t {
+                <$t>::min(self, other)
+            }
+
+            fn max(self, other: $t) -> $t {
+                <$t>::max(self, other)
+            }
+
+            fn signum(self) -> $t {
+                <$t>::signum(self)
+            }
+
+            fn recip(self) -> $t {
+                <$t>::recip(self)
+            }
+
+            fn powi(self, n: i32) -> $t {
+                <$t>::powi(self, n)
+            }
+
+            fn sin(self) -> $t {
+                <$t>::sin(self)
+            }
+
+            fn cos(self) -> $t {
+                <$t>::cos(self)
+            }
+
+            fn acos(self) -> $t {
+                <$t>::acos(self)
+            }
+        }
+    };
+}
+
+impl_scalar!(f32);
+impl_scalar!(f64);
+
+#[cfg(test)]
+mod tests {
+    use crate::algebra::vec3::Vec3;
+    use super::*;
+
+    fn vec3<T: Scalar>(x: f64, y: f64, z: f64) -> Vec3<T> {
+        Vec3 { x: T::from_f64(x), y: T::from_f64(y), z: T::from_f64(z) }
+    }
+
+    // the same checks at both precisions, the values are picked so that exact comparisons hold
+    fn check_vec3<T: Scalar + std::fmt::Debug>() {
+        let a: Vec3<T> = vec3(3.0, 0.0, 4.0);
+        assert_eq!(a.magnitude(), T::from_f64(5.0));
+        assert_eq!(a.normalize(), vec3(0.6, 0.0, 0.8));
+        assert_eq!(a.dot(vec3(1.0, 2.0, 3.0)), T::from_f64(15.0));
+        assert_eq!(a.hadamard(vec3(2.0, 2.0, 0.5)), vec3(6.0, 0.0, 2.0));
+        assert_eq!(vec3::<T>(1.0, 0.0, 0.0).cross_product(vec3(0.0, 1.0, 0.0)), vec3(0.0, 0.0, 1.0));
+        assert_eq!(vec3::<T>(2.0, 4.0, 8.0).recip(), vec3(0.5, 0.25, 0.125));
+        assert_eq!(a.clamp(T::zero(), T::one()), vec3(1.0, 0.0, 1.0));
+        assert_eq!(a.lerp(vec3(5.0, 2.0, 0.0), T::from_f64(0.5)), vec3(4.0, 1.0, 2.0));
+        assert_eq!(vec3::<T>(1.0, -1.0, 0.0).reflect(vec3(0.0, 1.0, 0.0)), vec3(1.0, 1.0, 0.0));
+        assert_eq!(-a + a * T::from_f64(2.0) - a / T::one(), vec3(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn vec3_works_with_f32() {
+        check_vec3::<f32>();
+    }
+
+    #[test]
+    fn vec3_works_with_f64() {
+        check_vec3::<f64>();
+    }
+}
@@ -0,0 +1,37 @@
+use crate::algebra::scalar::Scalar;
+
+// hermite falloff, 0 below edge0 and 1 above edge1
+pub fn smoothstep<T: Scalar>(edge0: T, edge1: T, x: T) -> T {
+    let t = ((x - edge0) / (edge1 - edge0)).max(T::zero()).min(T::one());
+    let three = T::from_f64(3.0);
+    t * t * (three - (t + t))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algebra::vec3::Vec3;
+    use super::*;
+
+    #[test]
+    fn smoothstep_is_flat_outside_the_edges() {
+        assert_eq!(smoothstep(1.0, 3.0, 0.5), 0.0);
+        assert_eq!(smoothstep(1.0, 3.0, 1.0), 0.0);
+        assert_eq!(smoothstep(1.0, 3.0, 3.0), 1.0);
+        assert_eq!(smoothstep(1.0, 3.0, 7.0), 1.0);
+    }
+
+    #[test]
+    fn smoothstep_is_half_at_the_midpoint() {
+        assert_eq!(smoothstep(1.0, 3.0, 2.0), 0.5);
+        assert_eq!(smoothstep(-1.0f32, 1.0, 0.0), 0.5);
+    }
+
+    #[test]
+    fn vec3_smoothstep_is_per_component() {
+        let edge0 = Vec3 { x: 0.0, y: 10.0, z: -2.0 };
+        let edge1 = Vec3 { x: 1.0, y: 20.0, z: 2.0 };
+        let v = Vec3 { x: -1.0, y: 15.0, z: 5.0 };
+        assert_eq!(v.smoothstep(edge0, edge1), Vec3 { x: 0.0, y: 0.5, z: 1.0 });
+        assert_eq!(Vec3 { x: -1.0, y: 0.5, z: 2.0 }.clamp(0.0, 1.0), Vec3 { x: 0.0, y: 0.5, z: 1.0 });
+    }
+}
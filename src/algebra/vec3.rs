@@ -1,23 +1,39 @@
 use std::ops;
 use std::fmt::Display;
-use num::Float;
+use approx::{AbsDiffEq, RelativeEq};
+use crate::algebra::scalar::Scalar;
+use crate::algebra::smoothstep::smoothstep;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Vec3<T> {
     pub x: T,
     pub y: T,
     pub z: T,
 }
 
-impl<T: Float> Vec3<T> {
+impl<T: Scalar> Vec3<T> {
     pub fn magnitude(self: Vec3<T>) -> T {
         (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
     }
 
+    // a zero length vector has no direction, normalizing it gives NaN components
     pub fn normalize(self: Vec3<T>) -> Vec3<T> {
         self / self.magnitude()
     }
 
+    pub fn dot(self: Vec3<T>, rhs: Vec3<T>) -> T {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+    }
+
+    // component-wise product, used to tint a color by another
+    pub fn hadamard(self: Vec3<T>, rhs: Vec3<T>) -> Vec3<T> {
+        Vec3 {
+            x: self.x * rhs.x,
+            y: self.y * rhs.y,
+            z: self.z * rhs.z,
+        }
+    }
+
     pub fn cross_product(self: Vec3<T>, rhs: Vec3<T>) -> Vec3<T> {
         Vec3 {
             x: self.y * rhs.z - self.z * rhs.y,
@@ -25,9 +41,120 @@ impl<T: Float> Vec3<T> {
             z: self.x * rhs.y - self.y * rhs.x,
         }
     }
+
+    // tangent and bitangent completing a unit vector into a right-handed orthonormal frame
+    // (Duff et al., "Building an Orthonormal Basis, Revisited")
+    pub fn orthonormal_basis(self: Vec3<T>) -> (Vec3<T>, Vec3<T>) {
+        let one = T::one();
+        let sign = self.z.signum();
+        let a = -one / (sign + self.z);
+        let b = self.x * self.y * a;
+        let tangent = Vec3 { x: one + sign * self.x * self.x * a, y: sign * b, z: -sign * self.x };
+        let bitangent = Vec3 { x: b, y: sign + self.y * self.y * a, z: -self.y };
+        (tangent, bitangent)
+    }
+
+    // gram-schmidt on a forward direction and a rough up vector, returns the unit (right, up)
+    // pair orthogonal to forward so that right = forward x up
+    pub fn orthonormalize(self: Vec3<T>, up: Vec3<T>) -> (Vec3<T>, Vec3<T>) {
+        let forward = self.normalize();
+        let up = (up - forward * (up * forward)).normalize();
+        (forward.cross_product(up), up)
+    }
+
+    pub fn recip(self: Vec3<T>) -> Vec3<T> {
+        Vec3 {
+            x: self.x.recip(),
+            y: self.y.recip(),
+            z: self.z.recip(),
+        }
+    }
+
+    pub fn clamp(self: Vec3<T>, min: T, max: T) -> Vec3<T> {
+        Vec3 {
+            x: self.x.max(min).min(max),
+            y: self.y.max(min).min(max),
+            z: self.z.max(min).min(max),
+        }
+    }
+
+    pub fn smoothstep(self: Vec3<T>, edge0: Vec3<T>, edge1: Vec3<T>) -> Vec3<T> {
+        Vec3 {
+            x: smoothstep(edge0.x, edge1.x, self.x),
+            y: smoothstep(edge0.y, edge1.y, self.y),
+            z: smoothstep(edge0.z, edge1.z, self.z),
+        }
+    }
+
+    pub fn lerp(self: Vec3<T>, other: Vec3<T>, t: T) -> Vec3<T> {
+        self + (other - self) * t
+    }
+
+    // spherical interpolation between two unit vectors
+    // opposite vectors have no unique arc between them, one through an arbitrary perpendicular is taken
+    pub fn slerp(self: Vec3<T>, other: Vec3<T>, t: T) -> Vec3<T> {
+        let cos_theta = (self * other).max(-T::one()).min(T::one());
+
+        // nearly parallel vectors make sin(theta) vanish, a plain lerp is good enough there
+        if cos_theta > T::from_f64(0.9995) {
+            return self.lerp(other, t).normalize();
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        if sin_theta < T::from_f64(1e-6) {
+            let (perpendicular, _) = self.orthonormal_basis();
+            return self * (t * theta).cos() + perpendicular * (t * theta).sin();
+        }
+        self * (((T::one() - t) * theta).sin() / sin_theta) + other * ((t * theta).sin() / sin_theta)
+    }
+
+    pub fn reflect(self: Vec3<T>, normal: Vec3<T>) -> Vec3<T> {
+        self - normal * ((T::one() + T::one()) * (self * normal))
+    }
+
+    // snell's law for a unit incident direction, eta_ratio is the incident over transmitted index
+    pub fn refract(self: Vec3<T>, normal: Vec3<T>, eta_ratio: T) -> Vec3<T> {
+        let cos_theta = (-self * normal).min(T::one());
+        let perpendicular = (self + normal * cos_theta) * eta_ratio;
+        let parallel = normal * -(T::one() - perpendicular * perpendicular).abs().sqrt();
+        perpendicular + parallel
+    }
+
+    // pick between reflection and refraction the way a dielectric does, using schlick's
+    // approximation against the given random number in [0, 1), returns true when it reflected
+    pub fn reflect_refract(self: Vec3<T>, normal: Vec3<T>, eta_ratio: T, random: T) -> (Vec3<T>, bool) {
+        let one = T::one();
+        let cos_theta = (-self * normal).min(one);
+        let sin_theta = (one - cos_theta * cos_theta).sqrt();
+        let total_internal_reflection = eta_ratio * sin_theta > one;
+
+        let r0 = ((one - eta_ratio) / (one + eta_ratio)).powi(2);
+        let reflectance = r0 + (one - r0) * (one - cos_theta).powi(5);
+
+        if total_internal_reflection || reflectance > random {
+            (self.reflect(normal), true)
+        } else {
+            (self.refract(normal, eta_ratio), false)
+        }
+    }
+}
+
+// weighted mean of the samples, zero when the weights add up to nothing
+pub fn weighted_average(samples: &[(Vec3<f64>, f64)]) -> Vec3<f64> {
+    let zero = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
+    let (sum, total_weight) = samples
+        .iter()
+        .fold((zero, 0.0), |(sum, total), &(sample, weight)| (sum + sample * weight, total + weight));
+
+    if total_weight == 0.0 {
+        zero
+    } else {
+        sum / total_weight
+    }
 }
 
-impl<T: Float> ops::Add<Vec3<T>> for Vec3<T> {
+impl<T: Scalar> ops::Add<Vec3<T>> for Vec3<T> {
     type Output = Vec3<T>;
 
     fn add(self, rhs: Vec3<T>) -> Self::Output {
@@ -39,7 +166,7 @@ impl<T: Float> ops::Add<Vec3<T>> for Vec3<T> {
     }
 }
 
-impl<T: Float> ops::Sub<Vec3<T>> for Vec3<T> {
+impl<T: Scalar> ops::Sub<Vec3<T>> for Vec3<T> {
     type Output = Vec3<T>;
 
     fn sub(self, rhs: Vec3<T>) -> Self::Output {
@@ -51,7 +178,7 @@ impl<T: Float> ops::Sub<Vec3<T>> for Vec3<T> {
     }
 }
 
-impl<T: Float> ops::Div<T> for Vec3<T> {
+impl<T: Scalar> ops::Div<T> for Vec3<T> {
     type Output = Vec3<T>;
 
     fn div(self, rhs: T) -> Self::Output {
@@ -63,7 +190,7 @@ impl<T: Float> ops::Div<T> for Vec3<T> {
     }
 }
 
-impl<T: Float> ops::Mul<T> for Vec3<T> {
+impl<T: Scalar> ops::Mul<T> for Vec3<T> {
     type Output = Vec3<T>;
 
     fn mul(self, rhs: T) -> Self::Output {
@@ -75,15 +202,17 @@ impl<T: Float> ops::Mul<T> for Vec3<T> {
     }
 }
 
-impl<T: Float> ops::Mul<Vec3<T>> for Vec3<T> {
+// `a * b` between two vectors is the dot product and returns a scalar, not a component-wise
+// product, prefer the explicit `dot` (or `hadamard`) where it reads better
+impl<T: Scalar> ops::Mul<Vec3<T>> for Vec3<T> {
     type Output = T;
 
     fn mul(self, rhs: Vec3<T>) -> Self::Output {
-        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z
+        self.dot(rhs)
     }
 }
 
-impl<T: Float> ops::Neg for Vec3<T> {
+impl<T: Scalar> ops::Neg for Vec3<T> {
     type Output = Vec3<T>;
 
     fn neg(self) -> Self::Output {
@@ -95,8 +224,214 @@ impl<T: Float> ops::Neg for Vec3<T> {
     }
 }
 
-impl<T: Float + Display> Display for Vec3<T> {
+impl<T: Scalar + Display> Display for Vec3<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "x: {:.3}, y: {:.3}, z: {:.3}", self.x, self.y, self.z)
     }
 }
+
+impl<T: AbsDiffEq> AbsDiffEq for Vec3<T>
+where
+    T::Epsilon: Copy,
+{
+    type Epsilon = T::Epsilon;
+
+    fn default_epsilon() -> T::Epsilon {
+        T::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Vec3<T>, epsilon: T::Epsilon) -> bool {
+        self.x.abs_diff_eq(&other.x, epsilon)
+            && self.y.abs_diff_eq(&other.y, epsilon)
+            && self.z.abs_diff_eq(&other.z, epsilon)
+    }
+}
+
+impl<T: RelativeEq> RelativeEq for Vec3<T>
+where
+    T::Epsilon: Copy,
+{
+    fn default_max_relative() -> T::Epsilon {
+        T::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Vec3<T>, epsilon: T::Epsilon, max_relative: T::Epsilon) -> bool {
+        self.x.relative_eq(&other.x, epsilon, max_relative)
+            && self.y.relative_eq(&other.y, epsilon, max_relative)
+            && self.z.relative_eq(&other.z, epsilon, max_relative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::{assert_abs_diff_eq, assert_abs_diff_ne, assert_relative_eq, assert_relative_ne};
+
+    #[test]
+    fn dot_and_its_operator() {
+        let a = Vec3 { x: 1.0, y: 2.0, z: 3.0 };
+        let b = Vec3 { x: 4.0, y: -5.0, z: 6.0 };
+        assert_eq!(a.dot(b), 12.0);
+        assert_eq!(a * b, a.dot(b));
+    }
+
+    #[test]
+    fn hadamard_is_component_wise() {
+        let a = Vec3 { x: 1.0, y: 2.0, z: 3.0 };
+        assert_eq!(a.hadamard(Vec3 { x: 4.0, y: -5.0, z: 0.5 }), Vec3 { x: 4.0, y: -10.0, z: 1.5 });
+    }
+
+    #[test]
+    fn cross_product_is_right_handed() {
+        let x = Vec3 { x: 1.0, y: 0.0, z: 0.0 };
+        let y = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+        assert_eq!(x.cross_product(y), Vec3 { x: 0.0, y: 0.0, z: 1.0 });
+        assert_eq!(y.cross_product(x), Vec3 { x: 0.0, y: 0.0, z: -1.0 });
+        let a = Vec3 { x: 1.0, y: 2.0, z: 3.0 };
+        assert_eq!(a.cross_product(Vec3 { x: 4.0, y: 5.0, z: 6.0 }), Vec3 { x: -3.0, y: 6.0, z: -3.0 });
+    }
+
+    #[test]
+    fn normalize_gives_a_unit_vector() {
+        let v = Vec3 { x: 0.0, y: 3.0, z: -4.0 }.normalize();
+        assert_eq!(v, Vec3 { x: 0.0, y: 0.6, z: -0.8 });
+        assert_eq!(v.magnitude(), 1.0);
+    }
+
+    #[test]
+    fn normalizing_zero_gives_nan() {
+        let v = Vec3 { x: 0.0f64, y: 0.0, z: 0.0 }.normalize();
+        assert!(v.x.is_nan() && v.y.is_nan() && v.z.is_nan());
+    }
+
+    #[test]
+    fn orthonormalize_corrects_a_careless_up() {
+        let forward = Vec3 { x: 1.0, y: 2.0, z: 3.0 };
+        let (right, up) = forward.orthonormalize(Vec3 { x: 0.0, y: 1.0, z: 0.5 });
+        let unit_forward = forward.normalize();
+
+        assert_abs_diff_eq!(right.magnitude(), 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(up.magnitude(), 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(right.dot(up), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(right.dot(unit_forward), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(up.dot(unit_forward), 0.0, epsilon = 1e-12);
+        // the frame still looks down the original forward
+        assert_abs_diff_eq!(up.cross_product(right), unit_forward, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn orthonormalize_removes_the_forward_part_of_up() {
+        let (right, up) = Vec3 { x: 0.0, y: 0.0, z: 2.0 }.orthonormalize(Vec3 { x: 0.0, y: -1.0, z: 1.0 });
+        assert_eq!(up, Vec3 { x: 0.0, y: -1.0, z: 0.0 });
+        assert_eq!(right, Vec3 { x: 1.0, y: 0.0, z: 0.0 });
+    }
+
+    #[test]
+    fn orthonormal_basis_is_a_right_handed_frame() {
+        let normals = [
+            Vec3 { x: 0.0, y: 0.0, z: 1.0 },
+            Vec3 { x: 0.0, y: 0.0, z: -1.0 },
+            Vec3 { x: 1.0, y: 0.0, z: 0.0 },
+            Vec3 { x: 0.0, y: 1.0, z: -0.0 },
+            Vec3 { x: -0.6, y: 0.0, z: -0.0 }.normalize(),
+            Vec3 { x: 1.0, y: -2.0, z: -3.0 }.normalize(),
+            Vec3 { x: 1e-9, y: 0.0, z: -1.0 }.normalize(),
+        ];
+        for normal in normals {
+            let (tangent, bitangent) = normal.orthonormal_basis();
+            assert_abs_diff_eq!(tangent.magnitude(), 1.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(bitangent.magnitude(), 1.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(tangent.dot(bitangent), 0.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(tangent.dot(normal), 0.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(bitangent.dot(normal), 0.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(tangent.cross_product(bitangent), normal, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn weighted_average_of_two_samples() {
+        let samples = [(Vec3 { x: 1.0, y: 0.0, z: 4.0 }, 3.0), (Vec3 { x: 5.0, y: 8.0, z: 0.0 }, 1.0)];
+        assert_eq!(weighted_average(&samples), Vec3 { x: 2.0, y: 2.0, z: 3.0 });
+    }
+
+    #[test]
+    fn weighted_average_without_weight_is_zero() {
+        let zero = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
+        assert_eq!(weighted_average(&[(Vec3 { x: 1.0, y: 2.0, z: 3.0 }, 0.0)]), zero);
+        assert_eq!(weighted_average(&[]), zero);
+    }
+
+    #[test]
+    fn grazing_ray_reflects() {
+        let normal = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+        let incident = Vec3 { x: 1.0, y: -0.01, z: 0.0 }.normalize();
+        // schlick gives a reflectance above 0.9 this close to the surface
+        let (direction, reflected) = incident.reflect_refract(normal, 1.0 / 1.5, 0.9);
+        assert!(reflected);
+        assert_abs_diff_eq!(direction, Vec3 { x: incident.x, y: -incident.y, z: 0.0 }, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn total_internal_reflection_always_reflects() {
+        let normal = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+        let incident = Vec3 { x: 3.0f64.sqrt(), y: -1.0, z: 0.0 }.normalize();
+        let (direction, reflected) = incident.reflect_refract(normal, 1.5, 0.999);
+        assert!(reflected);
+        assert_abs_diff_eq!(direction, incident.reflect(normal), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn shallow_ray_refracts_following_snell() {
+        let normal = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+        let incident = Vec3 { x: 0.1, y: -1.0, z: 0.0 }.normalize();
+        let eta_ratio = 1.0 / 1.5;
+        let (direction, reflected) = incident.reflect_refract(normal, eta_ratio, 0.5);
+        assert!(!reflected);
+        assert_abs_diff_eq!(direction.magnitude(), 1.0, epsilon = 1e-12);
+        assert!(direction.y < 0.0);
+        // sin of the transmitted angle is eta_ratio times the sin of the incident one
+        assert_abs_diff_eq!(direction.x, eta_ratio * incident.x, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn slerp_halfway_between_axes_is_at_45_degrees() {
+        let x = Vec3 { x: 1.0, y: 0.0, z: 0.0 };
+        let y = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+        let halfway = x.slerp(y, 0.5);
+        let diagonal = 0.5f64.sqrt();
+        assert_abs_diff_eq!(halfway, Vec3 { x: diagonal, y: diagonal, z: 0.0 }, epsilon = 1e-12);
+        assert_abs_diff_eq!(halfway.magnitude(), 1.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(x.slerp(y, 0.0), x, epsilon = 1e-12);
+        assert_abs_diff_eq!(x.slerp(y, 1.0), y, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn slerp_between_opposite_vectors_stays_on_the_sphere() {
+        for a in [Vec3 { x: 1.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: -1.0 }] {
+            let halfway = a.slerp(-a, 0.5);
+            assert_abs_diff_eq!(halfway.magnitude(), 1.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(halfway.dot(a), 0.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(a.slerp(-a, 1.0), -a, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn recip_is_component_wise() {
+        assert_eq!(Vec3 { x: 2.0, y: 4.0, z: 8.0 }.recip(), Vec3 { x: 0.5, y: 0.25, z: 0.125 });
+        assert_eq!(Vec3 { x: 0.0, y: -0.0, z: 1.0 }.recip(), Vec3 { x: f64::INFINITY, y: f64::NEG_INFINITY, z: 1.0 });
+    }
+
+    #[test]
+    fn abs_diff_eq_at_and_beyond_the_tolerance() {
+        let a = Vec3 { x: 1.0, y: 2.0, z: 3.0 };
+        assert_abs_diff_eq!(a, Vec3 { x: 1.5, y: 2.0, z: 2.5 }, epsilon = 0.5);
+        assert_abs_diff_ne!(a, Vec3 { x: 1.0, y: 2.75, z: 3.0 }, epsilon = 0.5);
+    }
+
+    #[test]
+    fn relative_eq_at_and_beyond_the_tolerance() {
+        let a = Vec3 { x: 100.0, y: -100.0, z: 0.0 };
+        assert_relative_eq!(a, Vec3 { x: 101.0, y: -101.0, z: 0.0 }, epsilon = 0.0, max_relative = 0.01);
+        assert_relative_ne!(a, Vec3 { x: 102.0, y: -100.0, z: 0.0 }, epsilon = 0.0, max_relative = 0.01);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::algebra::vec3::Vec3;
+use crate::ray::Ray;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Camera {
+    pub position: Vec3<f64>,
+    pub forward: Vec3<f64>,
+    // only needs to be roughly orthogonal to forward, it is corrected when building rays
+    pub up: Vec3<f64>,
+    // vertical field of view in degrees
+    pub vfov: f64,
+}
+
+impl Default for Camera {
+    // at the origin looking down +z, image y grows along +y, the view spans [-0.5, 0.5] at z = 1
+    fn default() -> Camera {
+        Camera {
+            position: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+            forward: Vec3 { x: 0.0, y: 0.0, z: 1.0 },
+            up: Vec3 { x: 0.0, y: -1.0, z: 0.0 },
+            vfov: 2.0 * 0.5f64.atan().to_degrees(),
+        }
+    }
+}
+
+impl Camera {
+    // ray through the top left corner of pixel (x, y), (0, 0) being the top left of the image
+    pub fn ray_for_pixel(self: &Camera, x: u32, y: u32, width: u32, height: u32) -> Ray {
+        self.ray_for_point(x as f64, y as f64, width, height)
+    }
+
+    // same as ray_for_pixel with fractional pixel coordinates
+    // the direction is not normalized, it reaches the image plane one unit in front of the camera
+    pub fn ray_for_point(self: &Camera, x: f64, y: f64, width: u32, height: u32) -> Ray {
+        let forward = self.forward.normalize();
+        let (right, up) = forward.orthonormalize(self.up);
+
+        // both axes are scaled by the height so pixels stay square on wide images
+        let half_height = (self.vfov.to_radians() / 2.0).tan();
+        let vx = (x - (width / 2) as f64) / height as f64 * 2.0 * half_height;
+        let vy = (y - (height / 2) as f64) / height as f64 * 2.0 * half_height;
+
+        Ray::new(self.position, forward + right * vx - up * vy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use super::*;
+
+    #[test]
+    fn default_camera_spans_half_a_unit_each_way() {
+        let camera = Camera::default();
+        for (x, y) in [(0, 0), (320, 320), (639, 0), (100, 500), (639, 639)] {
+            let ray = camera.ray_for_pixel(x, y, 640, 640);
+            let expected = Vec3 { x: (x as f64 - 320.0) / 640.0, y: (y as f64 - 320.0) / 640.0, z: 1.0 };
+            assert_eq!(ray.origin, camera.position);
+            assert_abs_diff_eq!(ray.direction, expected, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn smaller_vfov_narrows_the_view() {
+        let wide = Camera::default().ray_for_pixel(0, 0, 640, 640).direction;
+        let narrow = Camera { vfov: 20.0, ..Camera::default() }.ray_for_pixel(0, 0, 640, 640).direction;
+        assert!(narrow.x.abs() / narrow.z < wide.x.abs() / wide.z);
+        assert!(narrow.y.abs() / narrow.z < wide.y.abs() / wide.z);
+        // tan(10°) at the top edge of the image plane
+        assert_abs_diff_eq!(narrow.y, -(10.0f64).to_radians().tan(), epsilon = 1e-12);
+    }
+}
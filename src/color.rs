@@ -0,0 +1,224 @@
+use approx::{AbsDiffEq, RelativeEq};
+use crate::algebra::vec3::Vec3;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    // build an opaque color from rgb channels in the [0, 255] range
+    // out of range values are clamped and rounded, a bare `as u8` would truncate
+    pub fn from_f64_channels(r: f64, g: f64, b: f64) -> Color {
+        Color {
+            r: r.clamp(0.0, 255.0).round() as u8,
+            g: g.clamp(0.0, 255.0).round() as u8,
+            b: b.clamp(0.0, 255.0).round() as u8,
+            a: 255,
+        }
+    }
+
+    // build an opaque color from rgb channels in the [0, 1] range
+    pub fn from_rgb_f64(r: f64, g: f64, b: f64) -> Color {
+        Color::from_f64_channels(r * 255.0, g * 255.0, b * 255.0)
+    }
+
+    // rgb channels in the [0, 1] range, alpha is ignored
+    pub fn to_rgb_f64(self: Color) -> (f64, f64, f64) {
+        (self.r as f64 / 255.0, self.g as f64 / 255.0, self.b as f64 / 255.0)
+    }
+
+    pub fn saturating_add(self: Color, rhs: Color) -> Color {
+        Color {
+            r: self.r.saturating_add(rhs.r),
+            g: self.g.saturating_add(rhs.g),
+            b: self.b.saturating_add(rhs.b),
+            a: self.a.saturating_add(rhs.a),
+        }
+    }
+
+    // linear mix towards rhs, t = 0 keeps self and t = 1 gives rhs
+    pub fn blend(self: Color, rhs: Color, t: f64) -> Color {
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).clamp(0.0, 255.0).round() as u8;
+        Color {
+            r: mix(self.r, rhs.r),
+            g: mix(self.g, rhs.g),
+            b: mix(self.b, rhs.b),
+            a: mix(self.a, rhs.a),
+        }
+    }
+}
+
+// reads the components as linear rgb in [0, 1], clamped, with an opaque alpha
+// no gamma is applied, same bytes as `to_rgba_bytes`
+impl From<Vec3<f64>> for Color {
+    fn from(color: Vec3<f64>) -> Color {
+        let [r, g, b, a] = to_rgba_bytes(color, 1.0);
+        Color { r, g, b, a }
+    }
+}
+
+// gives the rgb channels in [0, 1] as is, without undoing any gamma, alpha is dropped
+impl From<Color> for Vec3<f64> {
+    fn from(color: Color) -> Vec3<f64> {
+        let (x, y, z) = color.to_rgb_f64();
+        Vec3 { x, y, z }
+    }
+}
+
+// channels and alpha are compared in [0, 1] units, with the difference taken on the integer
+// channels first so that an epsilon of 1.0 / 255.0 tolerates exactly one step
+fn channel_differences(color: &Color, other: &Color) -> [(f64, f64); 4] {
+    let channels = |color: &Color| [color.r, color.g, color.b, color.a];
+    let (a, b) = (channels(color), channels(other));
+    [0, 1, 2, 3].map(|i| (a[i].abs_diff(b[i]) as f64 / 255.0, a[i].max(b[i]) as f64 / 255.0))
+}
+
+impl AbsDiffEq for Color {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Color, epsilon: f64) -> bool {
+        channel_differences(self, other)
+            .iter()
+            .all(|&(difference, _)| difference <= epsilon)
+    }
+}
+
+impl RelativeEq for Color {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    // like f64, within epsilon or within max_relative of the larger channel
+    fn relative_eq(&self, other: &Color, epsilon: f64, max_relative: f64) -> bool {
+        channel_differences(self, other)
+            .iter()
+            .all(|&(difference, largest)| difference <= epsilon || difference <= largest * max_relative)
+    }
+}
+
+// encode a linear rgb color and its alpha in [0, 1] straight into the rgba bytes of a pixels frame
+// channels are clamped and rounded, and like everywhere else in the renderer no gamma is applied
+pub fn to_rgba_bytes(color: Vec3<f64>, alpha: f64) -> [u8; 4] {
+    let encode = |channel: f64| (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [
+        encode(color.x),
+        encode(color.y),
+        encode(color.z),
+        (alpha.clamp(0.0, 1.0) * 255.0).round() as u8,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::{assert_abs_diff_eq, assert_abs_diff_ne, assert_relative_eq, assert_relative_ne};
+
+    fn gray(value: u8) -> Color {
+        Color { r: value, g: value, b: value, a: 255 }
+    }
+
+    #[test]
+    fn one_channel_step_is_within_one_step_epsilon() {
+        for value in 0..255 {
+            assert_abs_diff_eq!(gray(value), gray(value + 1), epsilon = 1.0 / 255.0);
+            assert_abs_diff_eq!(Color { a: value, ..gray(0) }, Color { a: value + 1, ..gray(0) }, epsilon = 1.0 / 255.0);
+        }
+    }
+
+    #[test]
+    fn two_channel_steps_are_beyond_one_step_epsilon() {
+        for value in 0..254 {
+            assert_abs_diff_ne!(gray(value), gray(value + 2), epsilon = 1.0 / 255.0);
+        }
+        assert_abs_diff_ne!(gray(7), gray(8));
+    }
+
+    #[test]
+    fn color_relative_eq_scales_with_the_channels() {
+        assert_relative_eq!(gray(200), gray(202), epsilon = 0.0, max_relative = 0.01);
+        assert_relative_ne!(gray(200), gray(203), epsilon = 0.0, max_relative = 0.01);
+    }
+
+    #[test]
+    fn opaque_colors_round_trip_through_vec3() {
+        let colors = [
+            Color { r: 0, g: 0, b: 0, a: 255 },
+            Color { r: 255, g: 255, b: 255, a: 255 },
+            Color { r: 255, g: 0, b: 0, a: 255 },
+            Color { r: 136, g: 47, b: 164, a: 255 },
+            Color { r: 1, g: 128, b: 254, a: 255 },
+        ];
+        for color in colors {
+            assert_eq!(Color::from(Vec3::from(color)), color);
+        }
+    }
+
+    #[test]
+    fn vec3_to_color_clamps_and_drops_alpha() {
+        let color = Color::from(Vec3 { x: 1.5, y: -0.5, z: 0.5 });
+        assert_eq!(color, Color { r: 255, g: 0, b: 128, a: 255 });
+        assert_eq!(Vec3::from(Color { r: 255, g: 0, b: 51, a: 0 }), Vec3 { x: 1.0, y: 0.0, z: 0.2 });
+    }
+
+    #[test]
+    fn linear_color_encodes_to_rgba_bytes() {
+        assert_eq!(to_rgba_bytes(Vec3 { x: 0.5, y: 0.2, z: 1.0 }, 1.0), [128, 51, 255, 255]);
+        assert_eq!(to_rgba_bytes(Vec3 { x: 2.0, y: -1.0, z: 0.0 }, 0.5), [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn channels_above_255_clamp_instead_of_wrapping() {
+        assert_eq!(Color::from_f64_channels(256.0, 1000.0, -5.0), Color { r: 255, g: 255, b: 0, a: 255 });
+        assert_eq!(Color::from_f64_channels(f64::INFINITY, 255.4, 254.6), Color { r: 255, g: 255, b: 255, a: 255 });
+    }
+
+    #[test]
+    fn channels_are_rounded() {
+        assert_eq!(Color::from_f64_channels(127.5, 0.4, 10.6), Color { r: 128, g: 0, b: 11, a: 255 });
+    }
+
+    #[test]
+    fn saturating_add_stops_at_255() {
+        let a = Color { r: 200, g: 10, b: 0, a: 255 };
+        let b = Color { r: 100, g: 20, b: 0, a: 0 };
+        assert_eq!(a.saturating_add(b), Color { r: 255, g: 30, b: 0, a: 255 });
+    }
+
+    #[test]
+    fn blend_mixes_every_channel() {
+        let black = Color { r: 0, g: 0, b: 0, a: 0 };
+        let white = Color { r: 255, g: 255, b: 255, a: 255 };
+        assert_eq!(black.blend(white, 0.0), black);
+        assert_eq!(black.blend(white, 1.0), white);
+        assert_eq!(black.blend(white, 0.5), Color { r: 128, g: 128, b: 128, a: 128 });
+        assert_eq!(Color { r: 100, g: 200, b: 50, a: 255 }.blend(black, 0.25), Color { r: 75, g: 150, b: 38, a: 191 });
+    }
+
+    #[test]
+    fn rgb_f64_round_trips() {
+        let colors = [
+            Color { r: 0, g: 0, b: 0, a: 255 },
+            Color { r: 255, g: 255, b: 255, a: 255 },
+            Color { r: 136, g: 47, b: 164, a: 255 },
+            Color { r: 1, g: 128, b: 254, a: 255 },
+        ];
+        for color in colors {
+            let (r, g, b) = color.to_rgb_f64();
+            assert_eq!(Color::from_rgb_f64(r, g, b), color);
+        }
+    }
+
+    #[test]
+    fn rgb_f64_ignores_alpha_and_clamps() {
+        assert_eq!(Color { r: 255, g: 0, b: 51, a: 7 }.to_rgb_f64(), (1.0, 0.0, 0.2));
+        assert_eq!(Color::from_rgb_f64(1.5, -0.5, 0.5), Color { r: 255, g: 0, b: 128, a: 255 });
+    }
+}
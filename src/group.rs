@@ -0,0 +1,94 @@
+use std::ops::Range;
+use crate::aabb::Aabb;
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
+use crate::sphere::Sphere;
+
+// spheres sharing one bounding box, members are only tested when the box is hit
+#[derive(PartialEq, Debug)]
+pub struct Group {
+    pub bounds: Aabb,
+    pub members: Vec<Sphere>,
+}
+
+impl Group {
+    pub fn new(members: Vec<Sphere>) -> Option<Group> {
+        let bounds = members
+            .iter()
+            .map(|sphere| sphere.bounding_box())
+            .reduce(Aabb::union)?;
+        Some(Group { bounds, members })
+    }
+
+    pub fn hit(self: &Group, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        if !self.bounds.hit(ray, interval.clone()) {
+            return None;
+        }
+
+        let mut closest: Option<HitRecord> = None;
+        for sphere in &self.members {
+            let closest_t = closest.map_or(interval.end, |hit| hit.t);
+            if let Some(hit) = sphere.hit_record(ray, interval.start..closest_t) {
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algebra::vec3::Vec3;
+    use crate::camera::Camera;
+    use crate::color::Color;
+    use crate::random::Random;
+    use crate::sphere::INTERSECTION_TESTS;
+    use crate::world::World;
+
+    fn members() -> Vec<Sphere> {
+        [(-1.0, 0.0, 20.0, 1.0), (1.5, 0.5, 22.0, 1.5), (0.0, -2.0, 18.0, 0.5)]
+            .map(|(x, y, z, radius)| {
+                let color = Color { r: (x * 50.0 + 100.0) as u8, g: z as u8, b: 0, a: 255 };
+                Sphere::new(Vec3 { x, y, z }, radius, color, 0.0).unwrap()
+            })
+            .to_vec()
+    }
+
+    #[test]
+    fn ray_missing_the_bounds_tests_no_member() {
+        let group = Group::new(members()).unwrap();
+        let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 1.0, y: 0.0, z: 1.0 });
+        let before = INTERSECTION_TESTS.with(|count| count.get());
+        assert!(group.hit(&ray, 0.0..f64::INFINITY).is_none());
+        assert_eq!(INTERSECTION_TESTS.with(|count| count.get()), before);
+
+        // one that enters the box tests every member
+        let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: 1.0 });
+        group.hit(&ray, 0.0..f64::INFINITY);
+        assert_eq!(INTERSECTION_TESTS.with(|count| count.get()), before + 3);
+    }
+
+    #[test]
+    fn grouped_world_matches_the_ungrouped_one() {
+        let background = Color { r: 255, g: 255, b: 255, a: 255 };
+        let mut ungrouped = World::new(Camera::default(), background);
+        for sphere in members() {
+            ungrouped.add(sphere);
+        }
+        let mut grouped = World::new(Camera::default(), background);
+        grouped.add_group(Group::new(members()).unwrap());
+
+        let mut random = Random::new(1);
+        let mut hits = 0;
+        for _ in 0..10_000 {
+            let direction = Vec3 { x: random.range(-0.2, 0.2), y: random.range(-0.2, 0.2), z: 1.0 };
+            let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, direction);
+            let expected = ungrouped.hit(&ray, 0.0..f64::INFINITY);
+            let hit = grouped.hit(&ray, 0.0..f64::INFINITY);
+            assert_eq!(hit.map(|hit| (hit.t, hit.color)), expected.map(|hit| (hit.t, hit.color)));
+            hits += expected.is_some() as u32;
+        }
+        assert!(hits > 100);
+    }
+}
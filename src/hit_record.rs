@@ -0,0 +1,18 @@
+use crate::algebra::vec3::Vec3;
+use crate::color::Color;
+
+#[derive(Copy, Clone)]
+pub struct HitRecord {
+    pub t: f64,
+    pub point: Vec3<f64>,
+    // always faces against the incoming ray, front_face tells whether it was flipped
+    pub normal: Vec3<f64>,
+    pub front_face: bool,
+    // tangent, bitangent and normal form a right-handed orthonormal frame
+    pub tangent: Vec3<f64>,
+    pub bitangent: Vec3<f64>,
+    pub color: Color,
+    pub reflectivity: f64,
+    pub u: f64,
+    pub v: f64,
+}
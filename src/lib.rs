@@ -0,0 +1,15 @@
+pub mod aabb;
+pub mod algebra;
+pub mod camera;
+pub mod color;
+pub mod group;
+pub mod hit_record;
+pub mod light;
+pub mod prelude;
+pub mod random;
+pub mod ray;
+pub mod render;
+pub mod scene;
+pub mod sphere;
+pub mod wavefront;
+pub mod world;
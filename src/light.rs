@@ -0,0 +1,7 @@
+use crate::algebra::vec3::Vec3;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Light {
+    pub position: Vec3<f64>,
+    pub intensity: f64,
+}
@@ -1,60 +1,58 @@
 use pixels::{Pixels, SurfaceTexture};
+use std::env;
 use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+use std::time::Instant;
 use winit::{
     dpi::LogicalSize,
     event::{Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::{Window, WindowBuilder},
 };
-mod algebra;
-use crate::algebra::vec3::Vec3;
-use crate::algebra::quadratic::compute_quadratic;
+use raytracing_101::prelude::*;
+use raytracing_101::render::{render_to_image, save_png, RenderOptions};
 
 const WIDTH: u32 = 640;
 const HEIGHT: u32 = 640;
+// where the frame goes when the window surface can't be created, unless --fallback-output is given
+// an existing file there is only replaced when the path is given explicitly
+const FALLBACK_OUTPUT: &str = "fallback.png";
 const DEFAULT_RESOLUTION: LogicalSize<f64> = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
 
-#[derive(Copy, Clone)]
-struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
-    a: u8,
-}
-
-
-#[derive(Copy, Clone)]
-struct Sphere {
-    coordinates: Vec3<f64>,
-    radius: f64,
-    color: Color,
+struct ApplicationState {
+    name: String,
+    window: Window,
+    resolution: LogicalSize<f64>,
+    world: World,
+    options: RenderOptions,
+    fallback_output: Option<String>,
 }
 
-struct Ray {
-    direction: Vec3<f64>, 
+// what draw does with the frame once it tried to create the pixels surface
+enum Presentation<P> {
+    Window(P),
+    // the surface couldn't be created, the frame is saved to disk instead
+    Fallback(String),
 }
 
-impl Sphere {
-    fn intersect(self: Sphere, ray: &Ray, origin: Vec3<f64>) -> (f64, f64) {
-        let co = origin - self.coordinates;
-        let a = ray.direction * ray.direction;
-        let b = 2.0 * (co * ray.direction);
-        let c = co * co - self.radius * self.radius;
-        compute_quadratic(a, b, c)
+// some backends can't create the surface, that isn't fatal since the frame can still be kept
+fn choose_presentation<P, E: Display>(surface: Result<P, E>) -> Presentation<P> {
+    match surface {
+        Ok(pixels) => Presentation::Window(pixels),
+        Err(err) => Presentation::Fallback(err.to_string()),
     }
 }
 
-struct World {
-    origin: Vec3<f64>,
-    objects: Vec<Sphere>,
-    background: Color,
-}
-
-struct ApplicationState {
-    name: String,
-    window: Window,
-    resolution: LogicalSize<f64>,
-    world: World
+// path given with --fallback-output, or default as long as that wouldn't overwrite anything
+fn fallback_path(explicit: Option<&str>, default: &str) -> Result<String, String> {
+    match explicit {
+        Some(path) => Ok(path.to_string()),
+        None if Path::new(default).exists() => {
+            Err(format!("{} already exists, pass --fallback-output to overwrite it", default))
+        }
+        None => Ok(default.to_string()),
+    }
 }
 
 impl ApplicationState {
@@ -63,52 +61,40 @@ impl ApplicationState {
     }
 
     fn draw(self: &ApplicationState) {
+        let width = self.resolution.width as u32;
+        let height = self.resolution.height as u32;
+
+        let start = Instant::now();
+        let buffer = render_to_image(&self.world, width, height, &self.options);
+        println!("Frame rendered in {:?}.", start.elapsed());
+
         let surface_texture = SurfaceTexture::new(
             self.window.inner_size().width,
             self.window.inner_size().height,
             &self.window,
         );
-        let mut pixels = Pixels::new(self.resolution.width as u32, self.resolution.height as u32, surface_texture).unwrap();
-
-        let frame = pixels.frame_mut();
-        let mut results: Vec<Color> = Vec::with_capacity((HEIGHT * WIDTH) as usize);
-
-        for y in -((HEIGHT/2) as i32)..(HEIGHT/2) as i32 {
-            for x in -((WIDTH/2) as i32)..(WIDTH/2) as i32 {
-                let vx = x as f64 / WIDTH as f64;
-                let vy = y as f64 / HEIGHT as f64;
-                let ray = Ray { direction: Vec3 { x: vx.into(), y: vy.into(), z: 1.0} };
-
-                // compute the closest sphere that intersects the ray if any
-                let mut closest_sphere: Option<&Sphere> = None;
-                for sphere in &self.world.objects {
-                    let mut closest_t = f64::INFINITY;
-                    let (t1, t2) = sphere.intersect(&ray, self.world.origin);
-                    if (1.0..closest_t).contains(&t1)  {
-                        closest_t = t1;
-                        closest_sphere = Some(sphere);
+        let mut pixels = match choose_presentation(Pixels::new(width, height, surface_texture)) {
+            Presentation::Window(pixels) => pixels,
+            Presentation::Fallback(err) => {
+                println!("Can't create the pixels surface: {}.", err);
+                match fallback_path(self.fallback_output.as_deref(), FALLBACK_OUTPUT) {
+                    Ok(output) => {
+                        println!("Saving the frame to {} instead.", output);
+                        if let Err(err) = save_png(Path::new(&output), &buffer, width, height) {
+                            println!("Can't write the image: {}", err);
+                        }
                     }
-                    if (1.0..closest_t).contains(&t2) {
-                        closest_t = t2;
-                        closest_sphere = Some(sphere);
-                    }
-                }
-                if closest_sphere.is_some() {
-                    results.push(closest_sphere.unwrap().color);
-                } else {
-                    results.push(self.world.background);
+                    Err(err) => println!("Can't save the frame: {}.", err),
                 }
+                return;
             }
-        }
+        };
 
-        // set pixels color for every pixel of the frame
-        for (x, pixel) in frame.chunks_exact_mut(4).enumerate() {
-            pixel[0] = results[x].r;
-            pixel[1] = results[x].g;
-            pixel[2] = results[x].b;
-            pixel[3] = results[x].a;
+        pixels.frame_mut().copy_from_slice(&buffer);
+        if let Err(err) = pixels.render() {
+            println!("Can't present the frame: {}", err);
+            return;
         }
-        pixels.render().unwrap();
 
         self.window.request_redraw();
     }
@@ -120,7 +106,13 @@ struct Application {
 }
 
 impl Application {
-    pub fn new(name: String, resolution: Option<LogicalSize<f64>>) -> Result<Self, Box<dyn Error>> {
+    pub fn new(
+        name: String,
+        resolution: Option<LogicalSize<f64>>,
+        world: World,
+        options: RenderOptions,
+        fallback_output: Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
         let event_loop = EventLoop::new()?;
         event_loop.set_control_flow(ControlFlow::Wait);
 
@@ -131,30 +123,7 @@ impl Application {
 
         let window = Application::init_window(&name, resolution, &event_loop)?;
 
-        let mut world = World {
-            origin: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
-            objects: Vec::new(),
-            background: Color { r: 255, g: 255, b: 255, a: 255 },
-        };
-        let sphere_1 = Sphere {
-            coordinates: Vec3 { x: 0.0, y: 0.0, z: 30.0 },
-            radius: 5.0,
-            color: Color { r: 136, g: 47, b: 164, a: 255 },
-        };
-        let sphere_2 = Sphere {
-            coordinates: Vec3 { x: 2.5, y: 2.5, z: 23.0 },
-            radius: 5.0,
-            color: Color { r: 255, g: 0, b: 0, a: 255 },
-        };
-        let sphere_3 = Sphere {
-            coordinates: Vec3 { x: 2.5, y: 2.5, z: 25.0 },
-            radius: 5.0,
-            color: Color { r: 0, g: 0, b: 255, a: 255 },
-        };
-        world.objects.push(sphere_1);
-        world.objects.push(sphere_2);
-        world.objects.push(sphere_3);
-        let state = ApplicationState { name, window, resolution, world };
+        let state = ApplicationState { name, window, resolution, world, options, fallback_output };
 
         Ok(Application {
             state,
@@ -196,11 +165,9 @@ impl Application {
             Event::WindowEvent {
                 event: WindowEvent::RedrawRequested,
                 ..
-            } => {
-                if !has_draw {
-                    self.state.draw();
-                    has_draw = true;
-                }
+            } if !has_draw => {
+                self.state.draw();
+                has_draw = true;
             }
             _ => (),
         });
@@ -208,8 +175,133 @@ impl Application {
 }
 
 
+// the scene used when no scene file is given
+fn default_world() -> World {
+    let mut world = World::new(
+        Camera::default(),
+        Color { r: 255, g: 255, b: 255, a: 255 },
+    );
+    let sphere_1 = Sphere {
+        coordinates: Vec3 { x: 0.0, y: 0.0, z: 30.0 },
+        radius: 5.0,
+        color: Color { r: 136, g: 47, b: 164, a: 255 },
+        reflectivity: 0.0,
+    };
+    let sphere_2 = Sphere {
+        coordinates: Vec3 { x: 2.5, y: 2.5, z: 23.0 },
+        radius: 5.0,
+        color: Color { r: 255, g: 0, b: 0, a: 255 },
+        reflectivity: 0.0,
+    };
+    let sphere_3 = Sphere {
+        coordinates: Vec3 { x: 2.5, y: 2.5, z: 25.0 },
+        radius: 5.0,
+        color: Color { r: 0, g: 0, b: 255, a: 255 },
+        reflectivity: 0.0,
+    };
+    world.add(sphere_1);
+    world.add(sphere_2);
+    world.add(sphere_3);
+    world.lights.push(Light {
+        position: Vec3 { x: -20.0, y: -30.0, z: 0.0 },
+        intensity: 0.9,
+    });
+    world
+}
+
+struct Arguments {
+    scene: Option<String>,
+    output: Option<String>,
+    fallback_output: Option<String>,
+    width: u32,
+    height: u32,
+    options: RenderOptions,
+}
+
+// usage: raytracing_101 [scene file] [--output file.png] [--fallback-output file.png] [--width w] [--height h]
+//                      [--samples n] [--min-feature-size px] [--wavefront]
+fn parse_arguments() -> Result<Arguments, String> {
+    let mut arguments = Arguments {
+        scene: None,
+        output: None,
+        fallback_output: None,
+        width: WIDTH,
+        height: HEIGHT,
+        options: RenderOptions::default(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => arguments.output = Some(args.next().ok_or("--output expects a file name")?),
+            "--fallback-output" => {
+                arguments.fallback_output = Some(args.next().ok_or("--fallback-output expects a file name")?);
+            }
+            "--wavefront" => arguments.options.wavefront = true,
+            "--min-feature-size" => {
+                let value = args.next().ok_or("--min-feature-size expects a number")?;
+                let value: f64 = value.parse().map_err(|_| format!("invalid --min-feature-size \"{}\"", value))?;
+                if !(value.is_finite() && value > 0.0) {
+                    return Err("--min-feature-size must be > 0".to_string());
+                }
+                arguments.options.min_feature_size = value;
+            }
+            "--width" | "--height" | "--samples" => {
+                let value = args.next().ok_or(format!("{} expects a number", arg))?;
+                let value: u32 = value.parse().map_err(|_| format!("invalid {} \"{}\"", arg, value))?;
+                if value == 0 {
+                    return Err(format!("{} must be > 0", arg));
+                }
+                match arg.as_str() {
+                    "--width" => arguments.width = value,
+                    "--height" => arguments.height = value,
+                    _ => arguments.options.samples_per_pixel = value,
+                }
+            }
+            _ => arguments.scene = Some(arg),
+        }
+    }
+    Ok(arguments)
+}
+
 fn main() {
-    let application = Application::new(String::from("my wonderful application"), None);
+    let arguments = match parse_arguments() {
+        Ok(arguments) => arguments,
+        Err(err) => {
+            println!("Invalid arguments: {}", err);
+            return;
+        }
+    };
+
+    let world = match &arguments.scene {
+        Some(path) => World::from_file(Path::new(path)),
+        None => Ok(default_world()),
+    };
+    let world = match world {
+        Ok(world) => world,
+        Err(err) => {
+            println!("Can't load the scene: {}", err);
+            return;
+        }
+    };
+
+    // headless mode, render once and exit without opening a window
+    if let Some(output) = &arguments.output {
+        let buffer = render_to_image(&world, arguments.width, arguments.height, &arguments.options);
+        match save_png(Path::new(output), &buffer, arguments.width, arguments.height) {
+            Ok(()) => println!("Image written to {}.", output),
+            Err(err) => println!("Can't write the image: {}", err),
+        }
+        return;
+    }
+
+    let resolution = LogicalSize::new(arguments.width as f64, arguments.height as f64);
+    let application = Application::new(
+        String::from("my wonderful application"),
+        Some(resolution),
+        world,
+        arguments.options,
+        arguments.fallback_output,
+    );
     match application {
         Ok(application) => {
             println!("{} created. Running...", String::from(&application.state.name));
@@ -220,3 +312,34 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surface_error_chooses_the_fallback() {
+        let surface: Result<(), &str> = Err("no suitable adapter");
+        match choose_presentation(surface) {
+            Presentation::Fallback(err) => assert_eq!(err, "no suitable adapter"),
+            Presentation::Window(_) => panic!("expected the fallback"),
+        }
+    }
+
+    #[test]
+    fn created_surface_is_presented() {
+        let surface: Result<u32, &str> = Ok(7);
+        assert!(matches!(choose_presentation(surface), Presentation::Window(7)));
+    }
+
+    #[test]
+    fn default_fallback_never_overwrites() {
+        let path = env::temp_dir().join(format!("raytracing_101_fallback_{}.png", std::process::id()));
+        let default = path.to_str().unwrap();
+        assert_eq!(fallback_path(None, default), Ok(default.to_string()));
+        std::fs::write(&path, b"keep me").unwrap();
+        assert!(fallback_path(None, default).is_err());
+        assert_eq!(fallback_path(Some(default), FALLBACK_OUTPUT), Ok(default.to_string()));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
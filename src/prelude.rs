@@ -0,0 +1,10 @@
+pub use crate::aabb::Aabb;
+pub use crate::algebra::vec3::Vec3;
+pub use crate::camera::Camera;
+pub use crate::color::Color;
+pub use crate::group::Group;
+pub use crate::hit_record::HitRecord;
+pub use crate::light::Light;
+pub use crate::ray::Ray;
+pub use crate::sphere::{Sphere, SphereError};
+pub use crate::world::{ObjectId, World};
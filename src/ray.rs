@@ -0,0 +1,14 @@
+use crate::algebra::vec3::Vec3;
+
+pub struct Ray {
+    pub origin: Vec3<f64>,
+    pub direction: Vec3<f64>,
+    // 1 / direction per component, computed once for all the bounding box tests of the ray
+    pub inverse_direction: Vec3<f64>,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3<f64>, direction: Vec3<f64>) -> Ray {
+        Ray { origin, direction, inverse_direction: direction.recip() }
+    }
+}
@@ -0,0 +1,367 @@
+use rayon::prelude::*;
+use std::error::Error;
+use std::f64::consts::SQRT_2;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::algebra::vec3::Vec3;
+use crate::color::{to_rgba_bytes, Color};
+use crate::random::Random;
+use crate::ray::Ray;
+use crate::sphere::Sphere;
+use crate::wavefront::render_wavefront;
+use crate::world::World;
+
+// bounces allowed before reflective surfaces stop gathering more light
+pub(crate) const MAX_DEPTH: u32 = 5;
+// offset of reflected rays along the normal so they don't hit their own surface again
+pub(crate) const EPSILON: f64 = 1e-6;
+// primary rays start at the image plane, one unit in front of the camera
+pub(crate) const PRIMARY_T_MIN: f64 = 1.0;
+// finest thin feature grid, 4096 samples per pixel, features below sqrt(2) / 64 ≈ 0.022 pixels
+// across may still be missed
+pub const MAX_THIN_FEATURE_GRID: u32 = 64;
+
+// linear color seen along a ray, hits closer than t_min are ignored
+pub fn trace(world: &World, ray: &Ray, t_min: f64, depth: u32) -> Vec3<f64> {
+    // the closest sphere that intersects the ray if any
+    let hit = match world.hit(ray, t_min..f64::INFINITY) {
+        Some(hit) => hit,
+        None => return Vec3::from(world.background),
+    };
+
+    let color = world.shade(&hit);
+    if hit.reflectivity <= 0.0 || depth == 0 {
+        return color;
+    }
+
+    let origin = hit.point + hit.normal * EPSILON;
+    let reflected = Ray::new(origin, ray.direction.normalize().reflect(hit.normal));
+    color.lerp(trace(world, &reflected, 0.0, depth - 1), hit.reflectivity)
+}
+
+// camera rays of pixel (x, y), a single ray through its corner or jittered samples
+// seeded by the pixel so renders are reproducible
+// they start at the image plane, see PRIMARY_T_MIN
+pub fn primary_rays(world: &World, x: u32, y: u32, width: u32, height: u32, samples_per_pixel: u32) -> Vec<Ray> {
+    if samples_per_pixel <= 1 {
+        return vec![world.camera.ray_for_pixel(x, y, width, height)];
+    }
+
+    let mut random = Random::new((y * width + x) as u64);
+    (0..samples_per_pixel)
+        .map(|_| {
+            let sample_x = x as f64 + random.next_f64();
+            let sample_y = y as f64 + random.next_f64();
+            world.camera.ray_for_point(sample_x, sample_y, width, height)
+        })
+        .collect()
+}
+
+// same as primary_rays, but a pixel with a non zero grid is sampled at the center of each
+// cell of a grid x grid subdivision instead
+pub fn pixel_rays(
+    world: &World,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    samples_per_pixel: u32,
+    grid: u32,
+) -> Vec<Ray> {
+    if grid == 0 {
+        return primary_rays(world, x, y, width, height, samples_per_pixel);
+    }
+
+    let step = 1.0 / grid as f64;
+    (0..grid * grid)
+        .map(|cell| {
+            let sample_x = x as f64 + ((cell % grid) as f64 + 0.5) * step;
+            let sample_y = y as f64 + ((cell / grid) as f64 + 0.5) * step;
+            world.camera.ray_for_point(sample_x, sample_y, width, height)
+        })
+        .collect()
+}
+
+// grid size for every pixel in row-major order, 0 where the regular samples are enough
+// a disc of diameter d always contains a point of a grid with a spacing of at most d / sqrt(2),
+// so pixels overlapped by a sphere projecting smaller than that get a finer grid, sized for the
+// sphere or for min_feature_size if the sphere is even smaller, up to MAX_THIN_FEATURE_GRID
+pub fn thin_feature_grids(world: &World, width: u32, height: u32, options: &RenderOptions) -> Vec<u32> {
+    let mut grids = vec![0; (width * height) as usize];
+    if options.min_feature_size <= 0.0 {
+        return grids;
+    }
+
+    let camera = world.camera;
+    let forward = camera.forward.normalize();
+    let (right, up) = forward.orthonormalize(camera.up);
+    // pixels per unit on the image plane, one unit in front of the camera, see Camera::ray_for_point
+    let scale = height as f64 / (2.0 * (camera.vfov.to_radians() / 2.0).tan());
+    let regular = (options.samples_per_pixel as f64).sqrt().ceil() as u32;
+
+    let group_members = world.groups.iter().flat_map(|group| group.members.iter());
+    let spheres = world.objects.iter().map(|(_, sphere)| sphere).chain(group_members);
+    for sphere in spheres {
+        let Sphere { coordinates, radius, .. } = *sphere;
+        let offset = coordinates - camera.position;
+        let depth = offset * forward;
+        if depth <= 0.0 {
+            continue;
+        }
+
+        let diameter = 2.0 * radius / depth * scale;
+        let size = diameter.max(options.min_feature_size);
+        if size >= SQRT_2 {
+            continue;
+        }
+        let grid = ((SQRT_2 / size).ceil() as u32).min(MAX_THIN_FEATURE_GRID).max(regular);
+
+        // projected center in pixel coordinates, with half a pixel of slack around the disc
+        let center_x = offset * right / depth * scale + (width / 2) as f64;
+        let center_y = -(offset * up) / depth * scale + (height / 2) as f64;
+        let extent = diameter / 2.0 + 0.5;
+        let first_x = (center_x - extent).floor().max(0.0) as u32;
+        let first_y = (center_y - extent).floor().max(0.0) as u32;
+        let last_x = (center_x + extent).floor().min(width as f64 - 1.0);
+        let last_y = (center_y + extent).floor().min(height as f64 - 1.0);
+        if last_x < 0.0 || last_y < 0.0 {
+            continue;
+        }
+
+        for y in first_y..=last_y as u32 {
+            for x in first_x..=last_x as u32 {
+                let index = (y * width + x) as usize;
+                grids[index] = grids[index].max(grid);
+            }
+        }
+    }
+    grids
+}
+
+// linear color of a pixel, the average of its samples
+fn pixel_color(
+    world: &World,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    samples_per_pixel: u32,
+    grid: u32,
+) -> Vec3<f64> {
+    // the common single ray case, without collecting it into a Vec first
+    if samples_per_pixel <= 1 && grid == 0 {
+        let ray = world.camera.ray_for_pixel(x, y, width, height);
+        return trace(world, &ray, PRIMARY_T_MIN, MAX_DEPTH);
+    }
+
+    let rays = pixel_rays(world, x, y, width, height, samples_per_pixel, grid);
+    let sum = rays
+        .iter()
+        .fold(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, |sum, ray| sum + trace(world, ray, PRIMARY_T_MIN, MAX_DEPTH));
+    sum / rays.len() as f64
+}
+
+// (0, 0) is the top left pixel, samples are averaged in floating point and only
+// converted to a Color at the end, grid comes from thin_feature_grids (0 for regular samples)
+pub fn shade_pixel(
+    world: &World,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    samples_per_pixel: u32,
+    grid: u32,
+) -> Color {
+    Color::from(pixel_color(world, x, y, width, height, samples_per_pixel, grid))
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct RenderOptions {
+    pub samples_per_pixel: u32,
+    // process rays bounce by bounce instead of tracing each pixel recursively
+    pub wavefront: bool,
+    // spheres projecting down to this diameter in pixels are always sampled, see thin_feature_grids
+    // 0 turns the detection off, sizes below sqrt(2) / MAX_THIN_FEATURE_GRID get the finest grid
+    pub min_feature_size: f64,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions { samples_per_pixel: 1, wavefront: false, min_feature_size: 0.0 }
+    }
+}
+
+// colors of every pixel in row-major order, starting at the top left corner
+pub fn render(world: &World, width: u32, height: u32, options: &RenderOptions) -> Vec<Color> {
+    // never cancelled, so every pixel is there
+    render_cancellable(world, width, height, options, &AtomicBool::new(false))
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// same as render, but once cancel is set no new pixel (or ray in wavefront mode) is started
+// and the pixels that weren't finished are left as None
+pub fn render_cancellable(
+    world: &World,
+    width: u32,
+    height: u32,
+    options: &RenderOptions,
+    cancel: &AtomicBool,
+) -> Vec<Option<Color>> {
+    render_until(world, width, height, options, || cancel.load(Ordering::Relaxed))
+}
+
+// render_cancellable polling any condition, lets tests cancel after a given amount of work
+fn render_until(
+    world: &World,
+    width: u32,
+    height: u32,
+    options: &RenderOptions,
+    cancelled: impl Fn() -> bool + Sync,
+) -> Vec<Option<Color>> {
+    if options.wavefront {
+        return render_wavefront(world, width, height, options, cancelled);
+    }
+
+    let grids = thin_feature_grids(world, width, height, options);
+    (0..width * height)
+        .into_par_iter()
+        .map(|index| {
+            if cancelled() {
+                return None;
+            }
+            let (x, y) = (index % width, index / width);
+            Some(shade_pixel(world, x, y, width, height, options.samples_per_pixel, grids[index as usize]))
+        })
+        .collect()
+}
+
+// rgba bytes laid out like a pixels frame, four per pixel in row-major order
+pub fn to_rgba_buffer(colors: &[Color]) -> Vec<u8> {
+    colors
+        .iter()
+        .flat_map(|color| [color.r, color.g, color.b, color.a])
+        .collect()
+}
+
+// same bytes as to_rgba_buffer(&render(..)), but pixels are encoded straight from their linear color
+// the wavefront renderer still goes through Color since it only has colors once every bounce is done
+pub fn render_to_image(world: &World, width: u32, height: u32, options: &RenderOptions) -> Vec<u8> {
+    if options.wavefront {
+        return to_rgba_buffer(&render(world, width, height, options));
+    }
+
+    let grids = thin_feature_grids(world, width, height, options);
+    (0..width * height)
+        .into_par_iter()
+        .flat_map_iter(|index| {
+            let (x, y) = (index % width, index / width);
+            let color = pixel_color(world, x, y, width, height, options.samples_per_pixel, grids[index as usize]);
+            to_rgba_bytes(color, 1.0)
+        })
+        .collect()
+}
+
+pub fn save_png(path: &Path, buffer: &[u8], width: u32, height: u32) -> Result<(), Box<dyn Error>> {
+    image::save_buffer(path, buffer, width, height, image::ColorType::Rgba8)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use crate::camera::Camera;
+    use crate::world::random_scene;
+
+    fn white() -> Color {
+        Color { r: 255, g: 255, b: 255, a: 255 }
+    }
+
+    // a black sphere about a quarter of a pixel across, off the pixel corners
+    fn tiny_sphere_world() -> World {
+        let mut world = World::new(Camera::default(), white());
+        world.add(Sphere {
+            coordinates: Vec3 { x: 0.37, y: -0.21, z: 100.0 },
+            radius: 0.2,
+            color: Color { r: 0, g: 0, b: 0, a: 255 },
+            reflectivity: 0.0,
+        });
+        world
+    }
+
+    #[test]
+    fn sub_pixel_sphere_is_missed_without_thin_feature_detection() {
+        let colors = render(&tiny_sphere_world(), 64, 64, &RenderOptions::default());
+        assert!(colors.iter().all(|&color| color == white()));
+    }
+
+    #[test]
+    fn sub_pixel_sphere_is_detected_with_thin_feature_detection() {
+        for wavefront in [false, true] {
+            let options = RenderOptions { min_feature_size: 0.1, wavefront, ..RenderOptions::default() };
+            let colors = render(&tiny_sphere_world(), 64, 64, &options);
+            assert!(colors.iter().any(|&color| color != white()));
+        }
+    }
+
+    #[test]
+    fn thin_feature_grids_are_capped() {
+        // a millionth of a pixel across, an uncapped grid would overflow the sample count
+        let mut world = World::new(Camera::default(), white());
+        world.add(Sphere::new(Vec3 { x: 0.37, y: -0.21, z: 100.0 }, 1e-6, white(), 0.0).unwrap());
+        let options = RenderOptions { min_feature_size: 0.00001, ..RenderOptions::default() };
+        let grids = thin_feature_grids(&world, 64, 64, &options);
+        assert_eq!(grids.iter().max(), Some(&MAX_THIN_FEATURE_GRID));
+        assert_eq!(render(&world, 64, 64, &options).len(), 64 * 64);
+    }
+
+    #[test]
+    fn render_keeps_pixels_in_row_major_order() {
+        let world = tiny_sphere_world();
+        let options = RenderOptions { samples_per_pixel: 4, min_feature_size: 0.1, ..RenderOptions::default() };
+        let (width, height) = (48, 32);
+        let grids = thin_feature_grids(&world, width, height, &options);
+        let colors = render(&world, width, height, &options);
+        for (index, color) in colors.into_iter().enumerate() {
+            let (x, y) = (index as u32 % width, index as u32 / width);
+            assert_eq!(color, shade_pixel(&world, x, y, width, height, 4, grids[index]));
+        }
+    }
+
+    #[test]
+    fn render_to_image_matches_the_rendered_colors() {
+        for options in [
+            RenderOptions::default(),
+            RenderOptions { samples_per_pixel: 4, min_feature_size: 0.1, ..RenderOptions::default() },
+        ] {
+            let world = random_scene(30, 3);
+            let colors = render(&world, 48, 32, &options);
+            assert_eq!(render_to_image(&world, 48, 32, &options), to_rgba_buffer(&colors));
+        }
+    }
+
+    #[test]
+    fn cancelling_mid_render_leaves_a_partial_image() {
+        // one ray per pixel and no reflections, so every pixel started before cancelling finishes
+        let world = tiny_sphere_world();
+        let (width, height) = (64, 64);
+        for wavefront in [false, true] {
+            let options = RenderOptions { wavefront, ..RenderOptions::default() };
+            let polls = AtomicUsize::new(0);
+            let colors = render_until(&world, width, height, &options, || polls.fetch_add(1, Ordering::Relaxed) >= 1000);
+            assert_eq!(colors.len(), (width * height) as usize);
+            assert_eq!(colors.iter().filter(|color| color.is_some()).count(), 1000);
+        }
+    }
+
+    #[test]
+    fn cancelled_render_starts_nothing() {
+        for wavefront in [false, true] {
+            let options = RenderOptions { samples_per_pixel: 4, wavefront, ..RenderOptions::default() };
+            let colors = render_cancellable(&random_scene(30, 3), 32, 32, &options, &AtomicBool::new(true));
+            assert!(colors.iter().all(|color| color.is_none()));
+        }
+    }
+}
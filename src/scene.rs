@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use crate::algebra::vec3::Vec3;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::group::Group;
+use crate::light::Light;
+use crate::sphere::Sphere;
+use crate::world::{ObjectId, World};
+
+// on disk description of a world, either toml or json
+// the camera sits at origin with the default orientation unless a camera block is given
+#[derive(Serialize, Deserialize)]
+pub struct SceneDescription {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<[f64; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<CameraDescription>,
+    // ids are handed out from here, after the highest id in objects when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_id: Option<u64>,
+    pub background: ColorDescription,
+    #[serde(default)]
+    pub objects: Vec<SphereDescription>,
+    #[serde(default)]
+    pub groups: Vec<GroupDescription>,
+    #[serde(default)]
+    pub lights: Vec<LightDescription>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CameraDescription {
+    pub position: [f64; 3],
+    pub forward: [f64; 3],
+    pub up: [f64; 3],
+    pub vfov: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GroupDescription {
+    pub objects: Vec<SphereDescription>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SphereDescription {
+    // objects without an id get a fresh one, group members have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    pub coordinates: [f64; 3],
+    pub radius: f64,
+    pub color: ColorDescription,
+    #[serde(default)]
+    pub reflectivity: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LightDescription {
+    pub position: [f64; 3],
+    pub intensity: f64,
+}
+
+// either "#rrggbb" / "#rrggbbaa" or an [r, g, b, a] array
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ColorDescription {
+    Hex(String),
+    Rgba([u8; 4]),
+}
+
+impl ColorDescription {
+    fn to_color(self: &ColorDescription) -> Result<Color, Box<dyn Error>> {
+        match self {
+            ColorDescription::Rgba([r, g, b, a]) => Ok(Color { r: *r, g: *g, b: *b, a: *a }),
+            ColorDescription::Hex(hex) => {
+                let digits = hex.strip_prefix('#').unwrap_or(hex);
+                if !(digits.len() == 6 || digits.len() == 8) || !digits.is_ascii() {
+                    return Err(format!("invalid hex color \"{}\"", hex).into());
+                }
+                let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16);
+                let a = if digits.len() == 8 { channel(6)? } else { 255 };
+                Ok(Color { r: channel(0)?, g: channel(2)?, b: channel(4)?, a })
+            }
+        }
+    }
+}
+
+fn to_vec3(v: [f64; 3]) -> Vec3<f64> {
+    Vec3 { x: v[0], y: v[1], z: v[2] }
+}
+
+fn from_vec3(v: Vec3<f64>) -> [f64; 3] {
+    [v.x, v.y, v.z]
+}
+
+fn to_sphere(sphere: &SphereDescription, field: &str) -> Result<Sphere, Box<dyn Error>> {
+    let color = sphere.color.to_color().map_err(|err| format!("{}.color: {}", field, err))?;
+    let sphere = Sphere::new(to_vec3(sphere.coordinates), sphere.radius, color, sphere.reflectivity)
+        .map_err(|err| format!("{}.{}", field, err))?;
+    Ok(sphere)
+}
+
+fn from_sphere(id: Option<ObjectId>, sphere: &Sphere) -> SphereDescription {
+    let color = sphere.color;
+    SphereDescription {
+        id: id.map(|ObjectId(id)| id),
+        coordinates: from_vec3(sphere.coordinates),
+        radius: sphere.radius,
+        color: ColorDescription::Rgba([color.r, color.g, color.b, color.a]),
+        reflectivity: sphere.reflectivity,
+    }
+}
+
+impl SceneDescription {
+    pub fn parse(content: &str, extension: &str) -> Result<SceneDescription, Box<dyn Error>> {
+        match extension {
+            "toml" => Ok(toml::from_str(content)?),
+            "json" => Ok(serde_json::from_str(content)?),
+            _ => Err(format!("unsupported scene format \".{}\", expected .toml or .json", extension).into()),
+        }
+    }
+
+    pub fn serialize(self: &SceneDescription, extension: &str) -> Result<String, Box<dyn Error>> {
+        match extension {
+            "toml" => Ok(toml::to_string(self)?),
+            "json" => Ok(serde_json::to_string_pretty(self)?),
+            _ => Err(format!("unsupported scene format \".{}\", expected .toml or .json", extension).into()),
+        }
+    }
+
+    pub fn from_world(world: &World) -> SceneDescription {
+        let camera = world.camera;
+        let background = world.background;
+        SceneDescription {
+            origin: None,
+            camera: Some(CameraDescription {
+                position: from_vec3(camera.position),
+                forward: from_vec3(camera.forward),
+                up: from_vec3(camera.up),
+                vfov: camera.vfov,
+            }),
+            next_id: Some(world.next_id),
+            background: ColorDescription::Rgba([background.r, background.g, background.b, background.a]),
+            objects: world.objects.iter().map(|(id, sphere)| from_sphere(Some(*id), sphere)).collect(),
+            groups: world
+                .groups
+                .iter()
+                .map(|group| GroupDescription { objects: group.members.iter().map(|sphere| from_sphere(None, sphere)).collect() })
+                .collect(),
+            lights: world
+                .lights
+                .iter()
+                .map(|light| LightDescription { position: from_vec3(light.position), intensity: light.intensity })
+                .collect(),
+        }
+    }
+
+    pub fn to_world(self: &SceneDescription) -> Result<World, Box<dyn Error>> {
+        let camera = match &self.camera {
+            Some(camera) => Camera {
+                position: to_vec3(camera.position),
+                forward: to_vec3(camera.forward),
+                up: to_vec3(camera.up),
+                vfov: camera.vfov,
+            },
+            None => Camera {
+                position: to_vec3(self.origin.unwrap_or([0.0, 0.0, 0.0])),
+                ..Camera::default()
+            },
+        };
+        let mut world = World::new(camera, self.background.to_color()?);
+
+        // explicit ids are reserved first so objects without one can't take them
+        world.next_id = self.next_id.unwrap_or(0);
+        for (index, description) in self.objects.iter().enumerate() {
+            if let Some(id) = description.id {
+                let after = id.checked_add(1).ok_or_else(|| format!("objects[{}].id: {} leaves no id for new objects", index, id))?;
+                world.next_id = world.next_id.max(after);
+            }
+        }
+        for (index, description) in self.objects.iter().enumerate() {
+            let sphere = to_sphere(description, &format!("objects[{}]", index))?;
+            match description.id {
+                Some(id) if world.objects.iter().any(|(object_id, _)| *object_id == ObjectId(id)) => {
+                    return Err(format!("objects[{}].id: duplicate id {}", index, id).into());
+                }
+                Some(id) => world.objects.push((ObjectId(id), sphere)),
+                None => {
+                    world.add(sphere);
+                }
+            }
+        }
+        for (index, group) in self.groups.iter().enumerate() {
+            let members = group
+                .objects
+                .iter()
+                .enumerate()
+                .map(|(member, sphere)| {
+                    let field = format!("groups[{}].objects[{}]", index, member);
+                    if sphere.id.is_some() {
+                        return Err(format!("{}.id: group members have no ids", field).into());
+                    }
+                    to_sphere(sphere, &field)
+                })
+                .collect::<Result<Vec<Sphere>, Box<dyn Error>>>()?;
+            let group = Group::new(members).ok_or(format!("groups[{}].objects must not be empty", index))?;
+            world.add_group(group);
+        }
+        for light in &self.lights {
+            world.lights.push(Light {
+                position: to_vec3(light.position),
+                intensity: light.intensity,
+            });
+        }
+        Ok(world)
+    }
+}
+
+impl World {
+    // load a world from a .toml or .json scene file
+    pub fn from_file(path: &Path) -> Result<World, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("can't read scene file {}: {}", path.display(), err))?;
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        SceneDescription::parse(&content, extension)?.to_world()
+    }
+
+    // write the world and its camera to a .toml or .json scene file, from_file reads it back
+    // as the same world, object ids included
+    pub fn save(self: &World, path: &Path) -> Result<(), Box<dyn Error>> {
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        let content = SceneDescription::from_world(self).serialize(extension)?;
+        fs::write(path, content).map_err(|err| format!("can't write scene file {}: {}", path.display(), err))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light::Light;
+
+    fn edited_world() -> World {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/mirrors.toml");
+        let mut world = World::from_file(&path).unwrap();
+        let first = world.objects[0].0;
+        let middle = world.objects[1].0;
+        world.remove(middle).unwrap();
+        world.get_mut(first).unwrap().color = Color { r: 1, g: 2, b: 3, a: 4 };
+        let added = Sphere::new(Vec3 { x: 1.0 / 3.0, y: 0.1, z: 40.0 }, 0.7, Color { r: 9, g: 8, b: 7, a: 255 }, 0.25);
+        world.add(added.unwrap());
+        let member = Sphere::new(Vec3 { x: -3.0, y: 4.0, z: 50.0 }, 1.5, Color { r: 0, g: 255, b: 0, a: 255 }, 0.0);
+        world.add_group(Group::new(vec![member.unwrap()]).unwrap());
+        world.lights.push(Light { position: Vec3 { x: 10.0, y: -5.0, z: 2.5 }, intensity: 0.3 });
+        world.camera.forward = Vec3 { x: 0.1, y: 0.2, z: 0.9 };
+        world.camera.vfov = 33.3;
+        world.background = Color { r: 10, g: 20, b: 30, a: 40 };
+        world
+    }
+
+    #[test]
+    fn saved_world_loads_back_unchanged() {
+        for extension in ["toml", "json"] {
+            let mut world = edited_world();
+            let path = std::env::temp_dir().join(format!("raytracing_101_{}.{}", std::process::id(), extension));
+            world.save(&path).unwrap();
+            let mut loaded = World::from_file(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(loaded, world);
+            // the removed id stays retired
+            assert_eq!(loaded.add(world.objects[0].1), world.add(world.objects[0].1));
+        }
+    }
+
+    #[test]
+    fn unsupported_extension_is_rejected() {
+        let path = std::env::temp_dir().join(format!("raytracing_101_{}.yaml", std::process::id()));
+        assert!(edited_world().save(&path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected() {
+        let content = r##"
+            background = "#ffffff"
+            [[objects]]
+            id = 3
+            coordinates = [0.0, 0.0, 30.0]
+            radius = 1.0
+            color = "#ff0000"
+            [[objects]]
+            id = 3
+            coordinates = [0.0, 0.0, 40.0]
+            radius = 1.0
+            color = "#00ff00"
+        "##;
+        let error = SceneDescription::parse(content, "toml").unwrap().to_world().err().unwrap();
+        assert_eq!(error.to_string(), "objects[1].id: duplicate id 3");
+    }
+
+    #[test]
+    fn highest_possible_id_is_rejected() {
+        let content = format!(r##"
+            background = "#ffffff"
+            [[objects]]
+            id = {}
+            coordinates = [0.0, 0.0, 30.0]
+            radius = 1.0
+            color = "#ff0000"
+        "##, u64::MAX);
+        let error = SceneDescription::parse(&content, "toml").unwrap().to_world().err().unwrap();
+        assert_eq!(error.to_string(), format!("objects[0].id: {} leaves no id for new objects", u64::MAX));
+    }
+}
@@ -0,0 +1,265 @@
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt::Display;
+use std::ops::Range;
+use crate::aabb::Aabb;
+use crate::algebra::vec3::Vec3;
+use crate::algebra::quadratic::compute_quadratic;
+use crate::color::Color;
+use crate::hit_record::HitRecord;
+use crate::ray::Ray;
+#[cfg(feature = "wide")]
+use crate::algebra::simd::dot;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Sphere {
+    pub coordinates: Vec3<f64>,
+    pub radius: f64,
+    pub color: Color,
+    // 0.0 is matte, 1.0 a perfect mirror
+    pub reflectivity: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SphereError {
+    NonFiniteCenter,
+    InvalidRadius(f64),
+    InvalidReflectivity(f64),
+}
+
+impl Display for SphereError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SphereError::NonFiniteCenter => write!(f, "coordinates must be finite"),
+            SphereError::InvalidRadius(radius) => write!(f, "radius must be > 0, got {}", radius),
+            SphereError::InvalidReflectivity(reflectivity) => {
+                write!(f, "reflectivity must be within [0, 1], got {}", reflectivity)
+            }
+        }
+    }
+}
+
+impl Error for SphereError {}
+
+// the ray sphere test is the hot loop, with the wide feature its dot products use simd lanes
+#[cfg(not(feature = "wide"))]
+fn dot(a: Vec3<f64>, b: Vec3<f64>) -> f64 {
+    a.dot(b)
+}
+
+#[cfg(test)]
+thread_local! {
+    // ray sphere tests done on this thread, lets tests check what culling skips
+    pub(crate) static INTERSECTION_TESTS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+impl Sphere {
+    // the checked way to build a sphere, the fields can still be set directly for known good values
+    pub fn new(coordinates: Vec3<f64>, radius: f64, color: Color, reflectivity: f64) -> Result<Sphere, SphereError> {
+        if !(coordinates.x.is_finite() && coordinates.y.is_finite() && coordinates.z.is_finite()) {
+            return Err(SphereError::NonFiniteCenter);
+        }
+        if !(radius.is_finite() && radius > 0.0) {
+            return Err(SphereError::InvalidRadius(radius));
+        }
+        if !(0.0..=1.0).contains(&reflectivity) {
+            return Err(SphereError::InvalidReflectivity(reflectivity));
+        }
+        Ok(Sphere { coordinates, radius, color, reflectivity })
+    }
+
+    pub fn intersect(self: Sphere, ray: &Ray) -> (f64, f64) {
+        #[cfg(test)]
+        INTERSECTION_TESTS.with(|count| count.set(count.get() + 1));
+
+        let co = ray.origin - self.coordinates;
+        let a = dot(ray.direction, ray.direction);
+        let projection = dot(co, ray.direction);
+        let c = dot(co, co) - self.radius * self.radius;
+
+        // the ray line passes further than the radius from the center, skip the quadratic solve
+        // this is the discriminant (2 * projection)² - 4ac < 0 divided by 4, rounded the same way
+        if projection * projection < a * c {
+            return (f64::INFINITY, f64::INFINITY);
+        }
+
+        let b = 2.0 * projection;
+        let (t1, t2) = compute_quadratic(a, b, c);
+
+        // a tangent ray only grazes the surface, it doesn't count as a hit
+        if t1 == t2 {
+            return (f64::INFINITY, f64::INFINITY);
+        }
+        (t1, t2)
+    }
+
+    pub fn bounding_box(self: Sphere) -> Aabb {
+        let radius = self.radius.abs();
+        let extent = Vec3 { x: radius, y: radius, z: radius };
+        Aabb {
+            min: self.coordinates - extent,
+            max: self.coordinates + extent,
+        }
+    }
+
+    // outward unit normal at a point on the surface
+    pub fn normal_at(self: Sphere, point: Vec3<f64>) -> Vec3<f64> {
+        (point - self.coordinates).normalize()
+    }
+
+    // closest hit within the interval with everything shading needs
+    pub fn hit_record(self: Sphere, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        let (t1, t2) = self.intersect(ray);
+        let t = [t1, t2]
+            .into_iter()
+            .filter(|t| interval.contains(t))
+            .reduce(f64::min)?;
+
+        let point = ray.origin + ray.direction * t;
+        let outward_normal = self.normal_at(point);
+        let front_face = ray.direction.dot(outward_normal) < 0.0;
+        let normal = if front_face { outward_normal } else { -outward_normal };
+        let (tangent, bitangent) = normal.orthonormal_basis();
+
+        // spherical mapping, u wraps around the y axis and v goes from bottom to top
+        let u = ((-outward_normal.z).atan2(outward_normal.x) + PI) / (2.0 * PI);
+        let v = (-outward_normal.y).acos() / PI;
+
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            front_face,
+            tangent,
+            bitangent,
+            color: self.color,
+            reflectivity: self.reflectivity,
+            u,
+            v,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+    use crate::random::Random;
+    use super::*;
+
+    const RED: Color = Color { r: 255, g: 0, b: 0, a: 255 };
+
+    #[test]
+    fn new_accepts_a_valid_sphere() {
+        let center = Vec3 { x: 1.0, y: 2.0, z: 3.0 };
+        let sphere = Sphere::new(center, 0.5, RED, 0.25).unwrap();
+        assert_eq!(sphere, Sphere { coordinates: center, radius: 0.5, color: RED, reflectivity: 0.25 });
+    }
+
+    #[test]
+    fn new_rejects_invalid_spheres() {
+        let center = Vec3 { x: 0.0, y: 0.0, z: 10.0 };
+        assert_eq!(Sphere::new(center, 0.0, RED, 0.0), Err(SphereError::InvalidRadius(0.0)));
+        assert_eq!(Sphere::new(center, -1.0, RED, 0.0), Err(SphereError::InvalidRadius(-1.0)));
+        let nan_center = Vec3 { x: f64::NAN, ..center };
+        assert_eq!(Sphere::new(nan_center, 1.0, RED, 0.0), Err(SphereError::NonFiniteCenter));
+        assert_eq!(Sphere::new(center, 1.0, RED, 1.5), Err(SphereError::InvalidReflectivity(1.5)));
+    }
+
+    #[test]
+    fn exactly_tangent_ray_misses() {
+        let sphere = Sphere::new(Vec3 { x: 1.0, y: 0.0, z: 10.0 }, 1.0, RED, 0.0).unwrap();
+        let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: 1.0 });
+        assert_eq!(sphere.intersect(&ray), (f64::INFINITY, f64::INFINITY));
+        assert!(sphere.hit_record(&ray, 0.0..f64::INFINITY).is_none());
+    }
+
+    #[test]
+    fn hit_record_of_a_front_face_hit() {
+        let sphere = Sphere::new(Vec3 { x: 0.0, y: 0.0, z: 10.0 }, 2.0, RED, 0.3).unwrap();
+        let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: 1.0 });
+        let hit = sphere.hit_record(&ray, 0.0..f64::INFINITY).unwrap();
+
+        assert_eq!(hit.t, 8.0);
+        assert_eq!(hit.point, Vec3 { x: 0.0, y: 0.0, z: 8.0 });
+        assert_eq!(hit.normal, Vec3 { x: 0.0, y: 0.0, z: -1.0 });
+        assert!(hit.front_face);
+        assert_eq!(hit.tangent, Vec3 { x: 1.0, y: 0.0, z: 0.0 });
+        assert_eq!(hit.bitangent, Vec3 { x: 0.0, y: -1.0, z: 0.0 });
+        assert_eq!(hit.color, RED);
+        assert_eq!(hit.reflectivity, 0.3);
+        assert_abs_diff_eq!(hit.u, 0.75, epsilon = 1e-12);
+        assert_abs_diff_eq!(hit.v, 0.5, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn hit_record_from_inside_flips_the_normal() {
+        let sphere = Sphere::new(Vec3 { x: 0.0, y: 0.0, z: 10.0 }, 2.0, RED, 0.0).unwrap();
+        let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 10.0 }, Vec3 { x: 0.0, y: 0.0, z: 1.0 });
+        let hit = sphere.hit_record(&ray, 0.0..f64::INFINITY).unwrap();
+        assert_eq!(hit.t, 2.0);
+        assert!(!hit.front_face);
+        assert_eq!(hit.normal, Vec3 { x: 0.0, y: 0.0, z: -1.0 });
+        assert!(sphere.hit_record(&ray, 0.0..2.0).is_none());
+    }
+
+    // intersect without the projection fast path
+    fn full_solve(sphere: Sphere, ray: &Ray) -> (f64, f64) {
+        let co = ray.origin - sphere.coordinates;
+        let a = dot(ray.direction, ray.direction);
+        let b = 2.0 * dot(co, ray.direction);
+        let c = dot(co, co) - sphere.radius * sphere.radius;
+        match compute_quadratic(a, b, c) {
+            (t1, t2) if t1 == t2 => (f64::INFINITY, f64::INFINITY),
+            roots => roots,
+        }
+    }
+
+    #[test]
+    fn fast_path_matches_the_full_solve() {
+        let mut random = Random::new(217);
+        let mut hits = 0;
+        for _ in 0..2_000_000 {
+            let sphere = Sphere {
+                coordinates: Vec3 { x: random.range(-5.0, 5.0), y: random.range(-5.0, 5.0), z: random.range(5.0, 50.0) },
+                radius: random.range(0.1, 5.0),
+                color: RED,
+                reflectivity: 0.0,
+            };
+            let origin = Vec3 { x: random.range(-10.0, 10.0), y: random.range(-10.0, 10.0), z: random.range(-10.0, 10.0) };
+            // aimed around the sphere so that many rays pass close to its silhouette
+            let spread = 1.5 * sphere.radius;
+            let target = Vec3 { x: random.range(-spread, spread), y: random.range(-spread, spread), z: 0.0 };
+            let ray = Ray::new(origin, sphere.coordinates + target - origin);
+
+            let expected = full_solve(sphere, &ray);
+            assert_eq!(sphere.intersect(&ray), expected);
+            hits += expected.0.is_finite() as u32;
+        }
+        assert!(hits > 500_000);
+    }
+
+    #[test]
+    fn grazing_rays_match_the_full_solve() {
+        let mut random = Random::new(2170);
+        let mut hits = 0;
+        for _ in 0..300_000 {
+            let sphere = Sphere {
+                coordinates: Vec3 { x: random.range(-5.0, 5.0), y: random.range(-5.0, 5.0), z: random.range(5.0, 50.0) },
+                radius: random.range(0.01, 5.0),
+                color: RED,
+                reflectivity: 0.0,
+            };
+            let direction = Vec3 { x: random.range(-1.0, 1.0), y: random.range(-1.0, 1.0), z: 1.0 };
+            // the ray passes exactly one radius away from the center, up to rounding
+            let perpendicular = direction.cross_product(Vec3 { x: random.range(-1.0, 1.0), y: 1.0, z: 0.0 }).normalize();
+            let closest = sphere.coordinates + perpendicular * sphere.radius;
+            let ray = Ray::new(closest - direction * random.range(1.0, 30.0), direction);
+
+            let expected = full_solve(sphere, &ray);
+            assert_eq!(sphere.intersect(&ray), expected);
+            hits += expected.0.is_finite() as u32;
+        }
+        // rounding puts a good share of them on either side of the silhouette
+        assert!(hits > 30_000 && hits < 270_000);
+    }
+}
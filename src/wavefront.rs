@@ -0,0 +1,158 @@
+use rayon::prelude::*;
+use crate::algebra::vec3::Vec3;
+use crate::color::Color;
+use crate::ray::Ray;
+use crate::render::{pixel_rays, thin_feature_grids, RenderOptions, EPSILON, MAX_DEPTH, PRIMARY_T_MIN};
+use crate::world::World;
+
+// all the rays of one bounce, stored as separate arrays for better locality
+struct RayBatch {
+    origins: Vec<Vec3<f64>>,
+    directions: Vec<Vec3<f64>>,
+    t_mins: Vec<f64>,
+    // share of the final pixel color this ray carries
+    weights: Vec<f64>,
+    pixels: Vec<usize>,
+}
+
+impl RayBatch {
+    fn with_capacity(capacity: usize) -> RayBatch {
+        RayBatch {
+            origins: Vec::with_capacity(capacity),
+            directions: Vec::with_capacity(capacity),
+            t_mins: Vec::with_capacity(capacity),
+            weights: Vec::with_capacity(capacity),
+            pixels: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn push(self: &mut RayBatch, ray: Ray, t_min: f64, weight: f64, pixel: usize) {
+        self.origins.push(ray.origin);
+        self.directions.push(ray.direction);
+        self.t_mins.push(t_min);
+        self.weights.push(weight);
+        self.pixels.push(pixel);
+    }
+
+    fn len(self: &RayBatch) -> usize {
+        self.pixels.len()
+    }
+}
+
+// same image as render::render, but instead of tracing each pixel recursively every bounce
+// is processed as a whole: all active rays are intersected before any of them is shaded
+// cancelled is polled before each ray, once it returns true the pixels that still had rays in
+// flight are left as None
+pub fn render_wavefront(
+    world: &World,
+    width: u32,
+    height: u32,
+    options: &RenderOptions,
+    cancelled: impl Fn() -> bool + Sync,
+) -> Vec<Option<Color>> {
+    let pixel_count = (width * height) as usize;
+    let grids = thin_feature_grids(world, width, height, options);
+    let mut batch = RayBatch::with_capacity(pixel_count);
+    for (index, &grid) in grids.iter().enumerate() {
+        let (x, y) = (index as u32 % width, index as u32 / width);
+        let rays = pixel_rays(world, x, y, width, height, options.samples_per_pixel, grid);
+        let weight = 1.0 / rays.len() as f64;
+        for ray in rays {
+            batch.push(ray, PRIMARY_T_MIN, weight, index);
+        }
+    }
+
+    let mut accumulated = vec![Vec3 { x: 0.0, y: 0.0, z: 0.0 }; pixel_count];
+    for depth in (0..=MAX_DEPTH).rev() {
+        if batch.len() == 0 {
+            break;
+        }
+
+        // intersect and shade every ray of the bounce, keeping the reflected ray if any
+        // None for the rays skipped after cancelling
+        let results: Vec<_> = (0..batch.len())
+            .into_par_iter()
+            .map(|i| {
+                if cancelled() {
+                    return None;
+                }
+                let ray = Ray::new(batch.origins[i], batch.directions[i]);
+                let weight = batch.weights[i];
+                let hit = match world.hit(&ray, batch.t_mins[i]..f64::INFINITY) {
+                    Some(hit) => hit,
+                    None => return Some((Vec3::from(world.background) * weight, None)),
+                };
+
+                let color = world.shade(&hit);
+                if hit.reflectivity <= 0.0 || depth == 0 {
+                    return Some((color * weight, None));
+                }
+                let origin = hit.point + hit.normal * EPSILON;
+                let reflected = Ray::new(origin, ray.direction.normalize().reflect(hit.normal));
+                Some((color * (weight * (1.0 - hit.reflectivity)), Some((reflected, weight * hit.reflectivity))))
+            })
+            .collect();
+
+        let mut next = RayBatch::with_capacity(batch.len());
+        let mut skipped = Vec::new();
+        for (i, result) in results.into_iter().enumerate() {
+            let pixel = batch.pixels[i];
+            let Some((contribution, reflected)) = result else {
+                skipped.push(pixel);
+                continue;
+            };
+            accumulated[pixel] = accumulated[pixel] + contribution;
+            if let Some((ray, weight)) = reflected {
+                next.push(ray, 0.0, weight, pixel);
+            }
+        }
+        if !skipped.is_empty() {
+            let mut finished = vec![true; pixel_count];
+            for pixel in skipped.into_iter().chain(next.pixels) {
+                finished[pixel] = false;
+            }
+            return accumulated
+                .into_iter()
+                .zip(finished)
+                .map(|(color, finished)| finished.then(|| Color::from(color)))
+                .collect();
+        }
+        batch = next;
+    }
+
+    accumulated.into_iter().map(|color| Some(Color::from(color))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use approx::assert_abs_diff_eq;
+    use crate::render::render;
+    use crate::world::random_scene;
+    use super::*;
+
+    fn assert_renders_agree(world: &World, samples_per_pixel: u32) {
+        let recursive = RenderOptions { samples_per_pixel, ..RenderOptions::default() };
+        let wavefront = RenderOptions { wavefront: true, ..recursive };
+        let expected = render(world, 80, 60, &recursive);
+        let colors = render(world, 80, 60, &wavefront);
+        assert_eq!(colors.len(), expected.len());
+        for (color, expected) in colors.iter().zip(&expected) {
+            assert_abs_diff_eq!(color, expected, epsilon = 1.0 / 255.0);
+        }
+    }
+
+    #[test]
+    fn wavefront_matches_recursive_between_facing_mirrors() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/mirrors.toml");
+        let world = World::from_file(&path).unwrap();
+        assert_renders_agree(&world, 1);
+        assert_renders_agree(&world, 4);
+    }
+
+    #[test]
+    fn wavefront_matches_recursive_on_a_random_scene() {
+        assert_renders_agree(&random_scene(50, 7), 1);
+        assert_renders_agree(&random_scene(50, 7), 4);
+    }
+}
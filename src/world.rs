@@ -0,0 +1,245 @@
+use std::ops::Range;
+use crate::algebra::vec3::Vec3;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::group::Group;
+use crate::hit_record::HitRecord;
+use crate::light::Light;
+use crate::random::Random;
+use crate::ray::Ray;
+use crate::sphere::Sphere;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ObjectId(pub u64);
+
+// light every surface receives regardless of the lights
+const AMBIENT: f64 = 0.1;
+
+#[derive(PartialEq, Debug)]
+pub struct World {
+    pub camera: Camera,
+    pub objects: Vec<(ObjectId, Sphere)>,
+    pub groups: Vec<Group>,
+    pub lights: Vec<Light>,
+    pub background: Color,
+    // id given to the next added object, scene files keep it so saved ids are never reused
+    pub(crate) next_id: u64,
+}
+
+impl World {
+    pub fn new(camera: Camera, background: Color) -> World {
+        World {
+            camera,
+            objects: Vec::new(),
+            groups: Vec::new(),
+            lights: Vec::new(),
+            background,
+            next_id: 0,
+        }
+    }
+
+    pub fn add(self: &mut World, sphere: Sphere) -> ObjectId {
+        let id = ObjectId(self.next_id);
+        // a loaded scene can reserve ids up to u64::MAX, fail loudly rather than hand one out twice
+        self.next_id = self.next_id.checked_add(1).expect("no object ids left");
+        self.objects.push((id, sphere));
+        id
+    }
+
+    pub fn remove(self: &mut World, id: ObjectId) -> Option<Sphere> {
+        let index = self.objects.iter().position(|(object_id, _)| *object_id == id)?;
+        Some(self.objects.remove(index).1)
+    }
+
+    pub fn get_mut(self: &mut World, id: ObjectId) -> Option<&mut Sphere> {
+        self.objects
+            .iter_mut()
+            .find(|(object_id, _)| *object_id == id)
+            .map(|(_, sphere)| sphere)
+    }
+
+    pub fn add_group(self: &mut World, group: Group) {
+        self.groups.push(group);
+    }
+
+    // nearest hit among all objects and groups, the interval shrinks as closer hits are found
+    pub fn hit(self: &World, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        let mut closest: Option<HitRecord> = None;
+        for (_, sphere) in &self.objects {
+            let closest_t = closest.map_or(interval.end, |hit| hit.t);
+            if let Some(hit) = sphere.hit_record(ray, interval.start..closest_t) {
+                closest = Some(hit);
+            }
+        }
+        for group in &self.groups {
+            let closest_t = closest.map_or(interval.end, |hit| hit.t);
+            if let Some(hit) = group.hit(ray, interval.start..closest_t) {
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+
+    // the nearest hit of every object the ray crosses within the interval, in no particular order
+    pub fn hits<'a>(self: &'a World, ray: &'a Ray, interval: Range<f64>) -> impl Iterator<Item = HitRecord> + 'a {
+        let group_interval = interval.clone();
+        let group_members = self
+            .groups
+            .iter()
+            .filter(move |group| group.bounds.hit(ray, group_interval.clone()))
+            .flat_map(|group| group.members.iter());
+        self.objects
+            .iter()
+            .map(|(_, sphere)| sphere)
+            .chain(group_members)
+            .filter_map(move |sphere| sphere.hit_record(ray, interval.clone()))
+    }
+
+    // lambertian shading of a hit, summed over all lights plus the ambient term
+    // the result is linear rgb, it is only clamped when converted back to a Color
+    pub fn shade(self: &World, hit: &HitRecord) -> Vec3<f64> {
+        let mut intensity = AMBIENT;
+        for light in &self.lights {
+            let light_direction = (light.position - hit.point).normalize();
+            intensity += light.intensity * (hit.normal * light_direction).max(0.0);
+        }
+
+        Vec3::from(hit.color) * intensity
+    }
+
+    // append the objects, groups and lights of another world, optionally taking its background too
+    // merged objects get fresh ids from this world
+    pub fn merge(self: &mut World, other: World, override_background: bool) {
+        for (_, sphere) in other.objects {
+            self.add(sphere);
+        }
+        self.groups.extend(other.groups);
+        self.lights.extend(other.lights);
+        if override_background {
+            self.background = other.background;
+        }
+    }
+}
+
+// scatter n spheres in front of the camera, the same seed always gives the same world
+pub fn random_scene(n: usize, seed: u64) -> World {
+    let mut random = Random::new(seed);
+    let mut world = World::new(
+        Camera::default(),
+        Color { r: 255, g: 255, b: 255, a: 255 },
+    );
+    world.lights.push(Light {
+        position: Vec3 { x: -20.0, y: -30.0, z: 0.0 },
+        intensity: 0.9,
+    });
+
+    for _ in 0..n {
+        let sphere = Sphere {
+            coordinates: Vec3 {
+                x: random.range(-15.0, 15.0),
+                y: random.range(-15.0, 15.0),
+                z: random.range(20.0, 80.0),
+            },
+            radius: random.range(0.5, 3.0),
+            color: Color::from_rgb_f64(random.next_f64(), random.next_f64(), random.next_f64()),
+            reflectivity: if random.next_f64() < 0.2 { random.range(0.5, 1.0) } else { 0.0 },
+        };
+        world.add(sphere);
+    }
+    world
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sphere(z: f64, color: Color) -> Sphere {
+        Sphere::new(Vec3 { x: 0.0, y: 0.0, z }, 1.0, color, 0.0).unwrap()
+    }
+
+    fn forward_ray() -> Ray {
+        Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: 1.0 })
+    }
+
+    const RED: Color = Color { r: 255, g: 0, b: 0, a: 255 };
+    const GREEN: Color = Color { r: 0, g: 255, b: 0, a: 255 };
+    const BLUE: Color = Color { r: 0, g: 0, b: 255, a: 255 };
+
+    #[test]
+    fn closer_of_two_overlapping_spheres_wins() {
+        // listed far first, the near one must still win
+        for (first, second) in [(sphere(10.5, BLUE), sphere(10.0, RED)), (sphere(10.0, RED), sphere(10.5, BLUE))] {
+            let mut world = World::new(Camera::default(), GREEN);
+            world.add(first);
+            world.add(second);
+            let hit = world.hit(&forward_ray(), 0.0..f64::INFINITY).unwrap();
+            assert_eq!(hit.color, RED);
+            assert_eq!(hit.t, 9.0);
+        }
+    }
+
+    #[test]
+    fn hits_yields_every_stacked_sphere() {
+        let mut world = World::new(Camera::default(), GREEN);
+        world.add(sphere(30.0, BLUE));
+        world.add(sphere(10.0, RED));
+        world.add_group(Group::new(vec![sphere(20.0, GREEN)]).unwrap());
+
+        let mut hits: Vec<HitRecord> = world.hits(&forward_ray(), 0.0..f64::INFINITY).collect();
+        hits.sort_by(|a, b| a.t.total_cmp(&b.t));
+        let depths: Vec<f64> = hits.iter().map(|hit| hit.t).collect();
+        let colors: Vec<Color> = hits.iter().map(|hit| hit.color).collect();
+        assert_eq!(depths, [9.0, 19.0, 29.0]);
+        assert_eq!(colors, [RED, GREEN, BLUE]);
+    }
+
+    #[test]
+    fn ids_stay_valid_after_removing_another_object() {
+        let mut world = World::new(Camera::default(), GREEN);
+        let first = world.add(sphere(10.0, RED));
+        let middle = world.add(sphere(20.0, GREEN));
+        let last = world.add(sphere(30.0, BLUE));
+
+        assert_eq!(world.remove(middle).map(|sphere| sphere.color), Some(GREEN));
+        assert!(world.remove(middle).is_none());
+        assert!(world.get_mut(middle).is_none());
+
+        assert_eq!(world.get_mut(first).unwrap().color, RED);
+        world.get_mut(last).unwrap().radius = 2.0;
+        assert_eq!(world.objects, [(first, sphere(10.0, RED)), (last, Sphere { radius: 2.0, ..sphere(30.0, BLUE) })]);
+        // ids are never reused
+        assert_eq!(world.add(sphere(40.0, RED)), ObjectId(3));
+    }
+
+    #[test]
+    fn random_scene_depends_only_on_the_seed() {
+        let world = random_scene(20, 42);
+        assert_eq!(world.objects.len(), 20);
+        assert_eq!(random_scene(20, 42), world);
+        assert_ne!(random_scene(20, 43).objects, world.objects);
+    }
+
+    #[test]
+    fn merge_appends_objects_with_fresh_ids() {
+        let mut world = World::new(Camera::default(), GREEN);
+        world.add(sphere(10.0, RED));
+        let mut other = World::new(Camera::default(), BLUE);
+        other.add(sphere(20.0, GREEN));
+        other.add(sphere(30.0, BLUE));
+        other.lights.push(Light { position: Vec3 { x: 1.0, y: 2.0, z: 3.0 }, intensity: 0.5 });
+
+        world.merge(other, false);
+        let ids: Vec<ObjectId> = world.objects.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, [ObjectId(0), ObjectId(1), ObjectId(2)]);
+        assert_eq!(world.objects[2].1, sphere(30.0, BLUE));
+        assert_eq!(world.lights.len(), 1);
+        assert_eq!(world.background, GREEN);
+    }
+
+    #[test]
+    fn merge_can_take_the_other_background() {
+        let mut world = World::new(Camera::default(), GREEN);
+        world.merge(World::new(Camera::default(), BLUE), true);
+        assert_eq!(world.background, BLUE);
+    }
+}
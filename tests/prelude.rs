@@ -0,0 +1,18 @@
+use raytracing_101::prelude::*;
+
+#[test]
+fn prelude_is_enough_to_build_a_scene() {
+    let mut world = World::new(Camera::default(), Color { r: 255, g: 255, b: 255, a: 255 });
+    let red = Color { r: 255, g: 0, b: 0, a: 255 };
+    let id = world.add(Sphere::new(Vec3 { x: 0.0, y: 0.0, z: 10.0 }, 1.0, red, 0.0).unwrap());
+    let member = Sphere::new(Vec3 { x: 5.0, y: 0.0, z: 10.0 }, 1.0, red, 0.5).unwrap();
+    world.add_group(Group::new(vec![member]).unwrap());
+    world.lights.push(Light { position: Vec3 { x: 0.0, y: -10.0, z: 0.0 }, intensity: 0.8 });
+
+    let ray = Ray::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, Vec3 { x: 0.0, y: 0.0, z: 1.0 });
+    let hit: HitRecord = world.hit(&ray, 0.0..f64::INFINITY).unwrap();
+    assert_eq!(hit.color, red);
+    assert_eq!(id, ObjectId(0));
+    assert!(matches!(Sphere::new(Vec3 { x: 0.0, y: 0.0, z: 0.0 }, 0.0, red, 0.0), Err(SphereError::InvalidRadius(_))));
+    let _: Aabb = member.bounding_box();
+}